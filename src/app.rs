@@ -5,18 +5,32 @@ use std::process::exit;
 use std::cmp::{min, max};
 use regex::Regex;
 use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::reset_terminal;
+use crossterm::{execute, clipboard::CopyToClipboard};
 
 pub use crate::search::{search_ascii, search_hex, search_hex_ascii, search_hex_reverse,
-	convert_hexstring_to_vec, SearchResults, MatchType};
+	search_patterns, convert_hexstring_to_vec, SearchResults, MatchType};
+use crate::transform::{parse_hex_bytes, fill_pattern, fill_incrementing, fill_random, Transform};
+use crate::hash::{Algorithm, ChecksumPreset, SelectionDigest};
+use crate::state;
+use crate::marks::{self, Mark};
+use crate::expression::{self, parse_number, Context, NoContext, PointerFormat};
+use crate::stats::{self, ByteClass, ByteStats, FileMap};
+use crate::encoding::{Encoding, Glyph};
+use crate::inspector::{self, Field};
+use ratatui::layout::Rect;
 
 #[derive(PartialEq, Clone, Copy)]
 pub enum CurrentEditor {
 	HexEditor,
 	AsciiEditor,
 	CommandBar,
-	ExitPopup
+	ExitPopup,
+	StatsPopup,
+	ListPopup,
+	Inspector		// a field of the inspector is selected, to edit it with Enter
 }
 
 #[derive(Clone)]
@@ -25,6 +39,12 @@ pub struct CommandBar {
 	pub _cursor: u64
 }
 
+/// Popup displaying a list, like the one opened by `:marks`
+pub struct ListPopup {
+	pub title: String,
+	pub lines: Vec<String>
+}
+
 #[allow(unused)]
 pub enum WarningLevel {
 	Info,
@@ -38,18 +58,29 @@ pub enum Mode {
 	Insert
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 struct Inserted {
 	vector_address: u64,
 	offset_in_vector: u64
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone, Copy)]
 enum Addr {
 	FileAddress(u64),
 	InsertedAddress(Inserted)
 }
 
+/// How far we look around a change to clean self.modified_bytes.
+/// This is done on every write, so it must stay small
+const MAX_CLEANING_DISTANCE: u64 = 0x100;
+
+/// Most bytes added or removed at once by :resize and :insert. The unsaved changes
+/// and the history are kept in memory
+const MAX_RESIZED_BYTES: u64 = 0x400_0000;
+
+/// Number of positions remembered in the jump list (Ctrl + O / Ctrl + I)
+const MAX_JUMPS: usize = 100;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Changes {
 	Insertion(Vec<u8>),
@@ -63,7 +94,11 @@ pub enum Changes {
 pub enum Modification {
 	Insertion,
 	Modification,
-	Deletion
+	Deletion,
+	InsertedBlock,		// bytes inserted / deleted at once, the bytes are in `history_blocks`
+	DeletedBlock,
+	TransactionStart,	// the changes between a TransactionStart and a TransactionEnd
+	TransactionEnd		// are undone / redone in a single step
 }
 
 /// Different braille mode available for the Ascii pane display.
@@ -75,6 +110,104 @@ pub enum Braille {
 	Full	// Braille dump for all 255 values
 }
 
+/// How the bytes are displayed in the data pane, set with `:set view`
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub enum View {
+	Hex,
+	Oct,
+	Dec,
+	Bin
+}
+
+impl View {
+	pub fn parse(input: &str) -> Option<View> {
+		match input.trim() {
+			"hex" => Some(View::Hex),
+			"oct" => Some(View::Oct),
+			"dec" => Some(View::Dec),
+			"bin" => Some(View::Bin),
+			_ => None
+		}
+	}
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			View::Hex => "hex",
+			View::Oct => "oct",
+			View::Dec => "dec",
+			View::Bin => "bin"
+		}
+	}
+
+	pub fn radix(&self) -> u32 {
+		match self {
+			View::Hex => 16,
+			View::Oct => 8,
+			View::Dec => 10,
+			View::Bin => 2
+		}
+	}
+
+	/// Number of digits of a byte. The cursor has as many positions on each byte
+	pub fn digits(&self) -> u64 {
+		match self {
+			View::Hex => 2,
+			View::Oct => 3,
+			View::Dec => 3,
+			View::Bin => 8
+		}
+	}
+
+	/// Digits of `val`, padded with zeros
+	pub fn format(&self, val: u8) -> String {
+		match self {
+			View::Hex => format!("{:02x}", val),
+			View::Oct => format!("{:03o}", val),
+			View::Dec => format!("{:03}", val),
+			View::Bin => format!("{:08b}", val)
+		}
+	}
+
+	/// Replace the digit at `position` of `val` by `digit`. None if the result doesn't fit in a byte
+	pub fn replace_digit(&self, val: u8, position: u64, digit: u8) -> Option<u8> {
+		let radix = self.radix() as u16;
+		let weight = radix.pow((self.digits() - 1 - position) as u32);
+		let current_digit = (u16::from(val) / weight) % radix;
+
+		u8::try_from(u16::from(val) - current_digit * weight + u16::from(digit) * weight).ok()
+	}
+
+	/// Position of the first digit that differs between `a` and `b`. The last one if they are equal
+	pub fn first_different_digit(&self, a: u8, b: u8) -> u64 {
+		self.format(a).chars()
+			.zip(self.format(b).chars())
+			.position(|(a, b)| a != b)
+			.map_or(self.digits() - 1, |position| position as u64)
+	}
+}
+
+/// Number of bytes on each line, set with `--columns` or `:set columns`
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub enum Columns {
+	Fixed(u64),
+	Auto	// as many bytes as the width of the terminal allows, by groups of 8
+}
+
+impl Columns {
+	/// Most bytes on a line
+	pub const MAX: u64 = 0x100;
+
+	/// Parse `auto`, or a number of bytes
+	pub fn parse(input: &str) -> Option<Columns> {
+		match input.trim() {
+			"auto" => Some(Columns::Auto),
+			input => parse_number(input)
+				.filter(|columns| (1..=Columns::MAX).contains(columns))
+				.map(Columns::Fixed)
+		}
+	}
+}
+
 pub struct App {
 	reader: BufReader<File>,
 	pub file_path: String,
@@ -84,6 +217,13 @@ pub struct App {
 	pub cursor: u64,		// position of the cursor on the interface
 	pub lines_displayed: u16, // the number of lines currently displayed 
 							  // by the interface
+	pub columns: Columns,		// number of bytes per line asked by the user
+	pub bytes_per_line: u64,	// number of bytes per line displayed, updated by the ui in `Columns::Auto`
+	pub auto_columns_layout: Option<(u16, u64, u64)>,	// width, group size and digits used for the last `Columns::Auto` update
+	pub group_size: u64,		// bytes displayed together in the hex pane (`:set group`)
+	pub group_little_endian: bool,	// display the bytes of each group backward, like `xxd -e`
+	pub view: View,			// base of the data pane. The cursor has `view.digits()` positions on each byte
+	pub encoding: Encoding,		// encoding of the text pane, also used to write the chars typed in it
 	pub editor_mode: CurrentEditor,
 	pub command_bar: Option<CommandBar>,
 	pub search_results: Option<SearchResults>,
@@ -94,6 +234,8 @@ pub struct App {
 
 	pub history: Vec<(Modification, u64, Option<u8>)>,	// store the (Modification, address, old_value) of bytes edited for undo() 
 	history_redo: Vec<(Modification, u64, Option<u8>)>,	// used when we restore history. We can go back with redo()
+	history_blocks: Vec<Vec<u8>>,	// bytes of the InsertedBlock / DeletedBlock entries of self.history, in the same order
+	history_redo_blocks: Vec<Vec<u8>>,	// same for self.history_redo
 
 	// mode: overwrite, insert
 	pub mode: Mode,
@@ -102,9 +244,33 @@ pub struct App {
 	pub selection_start: Option<u64>, // Indicate the start of the selection,
 									  // chars between this address and the cusor
 									  // are selected.
+	pub clipboard: Option<Vec<u8>>,	// bytes copied or cut, that can be pasted
+	pub preview_hash: Option<Algorithm>,	// hash of the selection displayed in the preview pane
+	pub preview_digest: Option<SelectionDigest>,	// cache of this hash, computed by `update_preview_digest()`
+	pub preview_scroll: u16,	// lines scrolled in the preview pane, with `[` and `]`
+	pub changes_count: u64,		// incremented on each change of the bytes, to know when the caches are outdated
+	pub file_map: Option<FileMap>,	// cache of the entropy of the file, computed by `update_file_map()`
+	pub byte_stats: Option<ByteStats>,	// statistics displayed by `:stats`
+	pub list_popup: Option<ListPopup>,	// list displayed by `CurrentEditor::ListPopup`
+
+	pub marks: BTreeMap<char, Mark>,	// marks set with `m<a-z>`, saved for this file
+	marks_content_hash: Option<String>,	// hash of the file on the disk, the marks are saved with it
+	pub pending_key: Option<char>,		// first key of a shortcut of 2 keys, like `ma`
+
+	pub jump_list: Vec<(u64, u64)>,	// (cursor, offset) before the long jumps, to go back with Ctrl + O
+	follow_stack: Vec<(u64, u64)>,	// (cursor, offset) before following the pointers, to go back with `P`
+	pub follow_format: PointerFormat,	// integer read by `p`, `:follow` and `[...]` in the expressions
+	pub follow_base: u64,		// added to the pointers followed with `p`
+	jump_index: usize,		// where we are in `jump_list`. Equal to its length if we didn't go back
 
 	// interface customization options
+	pub base_address: u64,		// added to the file offsets to display the addresses (`:set base`)
 	pub show_infobar: bool,
+	pub show_entropy: bool,
+	pub show_minimap: bool,
+	pub show_inspector: bool,	// decode the bytes at the cursor in the inspector pane (`:inspector`)
+	pub inspector_index: usize,	// field of the inspector selected in `CurrentEditor::Inspector`
+	pub minimap_area: Rect,		// where the minimap is drawn, updated by the ui. Used to handle the clicks
 
 	pub last_address_read: u64,		// used by the app to keep track of where our reader is
 }
@@ -158,6 +324,13 @@ impl App {
 			file_size: size,
 			cursor: 0,
 			lines_displayed: 20, // updated when the ui is created
+			columns: Columns::Fixed(0x10),
+			bytes_per_line: 0x10,
+			auto_columns_layout: None,
+			group_size: 1,
+			group_little_endian: false,
+			view: View::Hex,
+			encoding: Encoding::Ascii,
 			editor_mode: CurrentEditor::HexEditor,
 			command_bar: None,
 			search_results: None,
@@ -165,13 +338,38 @@ impl App {
 			modified_bytes: BTreeMap::new(),
 			history: vec![],
 			history_redo: vec![],
+			history_blocks: vec![],
+			history_redo_blocks: vec![],
 			mode: mode,
 			selection_start: None,
+			clipboard: None,
+			preview_hash: None,
+			preview_digest: None,
+			preview_scroll: 0,
+			changes_count: 0,
+			file_map: None,
+			byte_stats: None,
+			list_popup: None,
+			marks: BTreeMap::new(),
+			marks_content_hash: None,
+			pending_key: None,
+			jump_list: vec![],
+			jump_index: 0,
+			follow_stack: vec![],
+			follow_format: PointerFormat::DEFAULT,
+			follow_base: 0,
 			braille: braille_mode,
+			base_address: 0,
 			show_infobar: true,
+			show_entropy: false,
+			show_minimap: false,
+			show_inspector: false,
+			inspector_index: 0,
+			minimap_area: Rect::default(),
 			last_address_read: 0,
 		};
 
+		app.load_marks();
 		app.jump_to(seek.unwrap_or(0));
 
 		Ok(app)
@@ -186,6 +384,83 @@ impl App {
 		self.file_size - self.offset
 	}
 
+	/// Address displayed for the file offset `offset`, with the base address
+	pub fn display_address(&self, offset: u64) -> u64 {
+		offset.saturating_add(self.base_address)
+	}
+
+	/// File offset of the display address `address`. The file offsets under the base address
+	/// are written `@0x10`, see `expression::evaluate_address()`
+	pub fn file_offset(&self, address: u64) -> Result<u64, String> {
+		address.checked_sub(self.base_address).ok_or(format!(
+			"0x{:x} is before the base address 0x{:x}, write @0x{:x} for a file offset",
+			address, self.base_address, address
+		))
+	}
+
+	/// Number of positions of the cursor on each byte
+	pub fn digits(&self) -> u64 {
+		self.view.digits()
+	}
+
+	/// Number of positions of the cursor on a line
+	pub fn line_digits(&self) -> u64 {
+		self.bytes_per_line * self.digits()
+	}
+
+	/// Change the base of the data pane. The positions saved with the cursor are converted
+	pub fn set_view(&mut self, view: View) {
+		let (old_digits, new_digits) = (self.digits(), view.digits());
+		let convert = |cursor: u64| (cursor / old_digits) * new_digits + min(cursor % old_digits, new_digits - 1);
+
+		self.cursor = convert(self.cursor);
+		self.selection_start = self.selection_start.map(convert);
+
+		for (cursor, _) in self.jump_list.iter_mut().chain(self.follow_stack.iter_mut()) {
+			*cursor = convert(*cursor);
+		}
+
+		self.view = view;
+	}
+
+	/// Change the number of bytes per line. The line of the cursor stays at the same place on the screen
+	pub fn set_bytes_per_line(&mut self, bytes_per_line: u64) {
+		if bytes_per_line == self.bytes_per_line || bytes_per_line == 0 {
+			return;
+		}
+
+		let address = self.cursor / self.digits();
+		let cursor_row = address.saturating_sub(self.offset) / self.bytes_per_line;
+
+		self.bytes_per_line = bytes_per_line;
+
+		let cursor_line_start = address - (address % bytes_per_line);
+		self.offset = cursor_line_start.saturating_sub(cursor_row * bytes_per_line);
+		self.reset();
+	}
+
+	/// Index in its line of the byte displayed at the position `position` of the hex pane.
+	/// The groups of bytes in little endian are displayed backward
+	pub fn displayed_index(&self, position: u64) -> u64 {
+		if !self.group_little_endian {
+			return position;
+		}
+
+		// the last group of the line can be incomplete
+		let group_start = position - (position % self.group_size);
+		let group_len = min(self.group_size, self.bytes_per_line - group_start);
+
+		group_start + group_len - 1 - (position % self.group_size)
+	}
+
+	/// Number of hex digits of the biggest address displayed, at least 8
+	pub fn address_width(&self) -> usize {
+		let last_address = self.display_address(self.file_size);
+		let digits = (u64::BITS - last_address.leading_zeros()).div_ceil(4) as usize;
+
+		digits.max(8)
+	}
+
 	pub fn add_error_message(&mut self, level: WarningLevel, message: String) {
 		self.error_msg = Some((level, message));
 	}
@@ -226,9 +501,25 @@ impl App {
 	/// no `inserted_bytes`. If we end up in the middle of an `self.inserted_bytes` vector
 	/// we return the key of self.modified_bytes hashmap, and the offset to the byte we are accessing
 	fn get_real_address(&self, address: u64) -> Addr {
+		self.walk_real_address(0, address)
+	}
+
+	/// Same as get_real_address(), for the byte `count` bytes after `real_address`.
+	/// Only the modified_bytes between them are walked
+	fn skip_real_address(&self, real_address: Addr, count: u64) -> Addr {
+		match real_address {
+			Addr::FileAddress(addr) => self.walk_real_address(addr, addr + count),
+			Addr::InsertedAddress(Inserted { vector_address, offset_in_vector }) =>
+				self.walk_real_address(vector_address, vector_address + offset_in_vector + count)
+		}
+	}
+
+	/// Walk the modified_bytes from the key `first_key`, `address` being the address
+	/// shifted by the modified_bytes before `first_key`
+	fn walk_real_address(&self, first_key: u64, address: u64) -> Addr {
 		let mut address = address;
 
-		for (modified_addr, changes) in &self.modified_bytes {
+		for (modified_addr, changes) in self.modified_bytes.range(first_key..) {
 
 			// only subtract addresses of bytes inserted
 			// before the address we are watching
@@ -265,47 +556,269 @@ impl App {
 		Addr::FileAddress(address)
 	}
 
-	/// Remove every modified_bytes contaning the current file byte value
-	fn clean_modified_bytes(&mut self) {
-		let mut real_addr_delta: i64 = 0;
-		let mut addrs_to_remove = vec![];
+	/// Remove the modified_bytes at `file_address` if it contains the current file byte value
+	fn clean_modified_bytes(&mut self, file_address: u64) {
+		self.restore_modified_run(file_address);
+		self.restore_deleted_bytes(file_address);
+		self.remove_overwrite_of_same_value(file_address);
 
-		for (modified_addr, changes) in self.modified_bytes.clone() {
-			let inserted_vec = match changes {
-				Changes::Deleted => {
-					real_addr_delta -= 1;
-					continue;
-				},
-				Changes::Insertion(inserted_vec) => {
-					inserted_vec
+		// Removing modified bytes can leave a run of modified bytes that is back to the file content
+		let before = self.modified_bytes.range(..file_address).next_back().map(|(address, _)| *address);
+		let after = self.modified_bytes.range(file_address..).next().map(|(address, _)| *address);
+
+		for address in before.into_iter().chain(after) {
+			self.restore_modified_run(address);
+		}
+	}
+
+	/// Remove the modified_bytes at `file_address` if it is an overwrite with the file byte value
+	fn remove_overwrite_of_same_value(&mut self, file_address: u64) {
+		// Only a vector with a single byte can be an overwrite of the file byte
+		let value = match self.modified_bytes.get(&file_address) {
+			Some(Changes::Insertion(inserted_vec)) if inserted_vec.len() == 1 => inserted_vec[0],
+			_ => return
+		};
+
+		// The keys of self.modified_bytes are addresses in self.file
+		if let Ok(file_byteval) = self.read_byte_addr_file(file_address) {
+			if file_byteval == value {
+				self.modified_bytes.remove(&file_address);
+			}
+		}
+	}
+
+	/// Clean self.modified_bytes after overwriting the modified_bytes from `start` to `end` (included).
+	/// Same as calling clean_modified_bytes() on each of them, but we read the file only once
+	fn clean_overwritten_bytes(&mut self, start: u64, end: u64) {
+		const CHUNK_SIZE: u64 = 0x10000;
+
+		// Remove the overwrites with the file byte value
+		let mut chunk_start = start;
+		while chunk_start <= end {
+			let chunk_end = min(end, chunk_start + CHUNK_SIZE - 1);
+			let file_bytes = self.read_file_bytes(chunk_start, (chunk_end - chunk_start + 1) as usize).unwrap_or_default();
+
+			let same_values: Vec<u64> = self.modified_bytes.range(chunk_start..=chunk_end)
+				.filter(|(address, changes)| match changes {
+					Changes::Insertion(inserted_vec) if inserted_vec.len() == 1 =>
+						file_bytes.get((**address - chunk_start) as usize) == Some(&inserted_vec[0]),
+					_ => false
+				})
+				.map(|(address, _)| *address)
+				.collect();
+
+			for address in same_values {
+				self.modified_bytes.remove(&address);
+			}
+
+			chunk_start = chunk_end + 1;
+		}
+
+		// Only the vectors of inserted bytes and the deleted bytes can be part of a run back to
+		// the file content, plus the runs going over both ends of the range
+		let other_changes: Vec<u64> = self.modified_bytes.range(start..=end)
+			.filter(|(_, changes)| !matches!(changes, Changes::Insertion(inserted_vec) if inserted_vec.len() == 1))
+			.map(|(address, _)| *address)
+			.collect();
+
+		for address in [start].into_iter().chain(other_changes).chain([end]) {
+			self.clean_modified_bytes(address);
+		}
+	}
+
+	/// When bytes deleted from the file are inserted back, they can end up in the vector
+	/// of a neighbour. Give them back to the deleted bytes, so that self.modified_bytes stays clean.
+	fn restore_deleted_bytes(&mut self, file_address: u64) {
+		let vec_len = match self.modified_bytes.get(&file_address) {
+			Some(Changes::Insertion(inserted_vec)) => inserted_vec.len() as u64,
+			_ => return
+		};
+
+		let deleted_before = self.count_deleted_bytes((0..file_address).rev());
+		let deleted_after = self.count_deleted_bytes(file_address + 1..);
+
+		if deleted_before == 0 && deleted_after == 0 {
+			return;
+		}
+
+		let before = min(deleted_before, vec_len);
+		let file_bytes_before = self.read_file_bytes(file_address - before, before as usize).unwrap_or_default();
+		let after = min(deleted_after, vec_len);
+		let file_bytes_after = self.read_file_bytes(file_address + 1, after as usize).unwrap_or_default();
+
+		let Some(Changes::Insertion(inserted_vec)) = self.modified_bytes.get_mut(&file_address) else { return };
+
+		// The first bytes of the vector can go back to the deleted bytes just before,
+		// and the last bytes to the deleted bytes just after
+		let restored_before = (1..=file_bytes_before.len()).rev()
+			.find(|&i| inserted_vec[..i] == file_bytes_before[file_bytes_before.len() - i..])
+			.unwrap_or(0);
+		inserted_vec.drain(..restored_before);
+
+		let restored_after = (1..=min(file_bytes_after.len(), inserted_vec.len())).rev()
+			.find(|&i| inserted_vec[inserted_vec.len() - i..] == file_bytes_after[..i])
+			.unwrap_or(0);
+		inserted_vec.truncate(inserted_vec.len() - restored_after);
+
+		if inserted_vec.is_empty() {
+			if self.read_byte_addr_file(file_address).is_ok() {
+				self.modified_bytes.insert(file_address, Changes::Deleted);
+			} else {
+				self.modified_bytes.remove(&file_address);
+			}
+		}
+
+		for i in 0..restored_before as u64 {
+			self.modified_bytes.remove(&(file_address - i - 1));
+		}
+		for i in 0..restored_after as u64 {
+			self.modified_bytes.remove(&(file_address + i + 1));
+		}
+	}
+
+	/// Count the consecutive deleted bytes at the `addresses`, up to MAX_CLEANING_DISTANCE
+	fn count_deleted_bytes(&self, addresses: impl Iterator<Item = u64>) -> u64 {
+		addresses
+			.take(MAX_CLEANING_DISTANCE as usize)
+			.take_while(|address| self.modified_bytes.get(address) == Some(&Changes::Deleted))
+			.count() as u64
+	}
+
+	/// If the consecutive modified file bytes around `file_address` contain the same bytes
+	/// as the file, e.g. after undoing every change, remove them from self.modified_bytes
+	fn restore_modified_run(&mut self, file_address: u64) {
+		if !self.modified_bytes.contains_key(&file_address) {
+			return;
+		}
+
+		let mut start = file_address;
+		while file_address - start < MAX_CLEANING_DISTANCE && start > 0 && self.modified_bytes.contains_key(&(start - 1)) {
+			start -= 1;
+		}
+		let mut end = file_address + 1;
+		while end - start < MAX_CLEANING_DISTANCE && self.modified_bytes.contains_key(&end) {
+			end += 1;
+		}
+
+		let run_content = || self.modified_bytes.range(start..end)
+			.map(|(_, changes)| match changes {
+				Changes::Insertion(inserted_vec) => inserted_vec.as_slice(),
+				Changes::Deleted => &[]
+			});
+
+		// The last vector can be after the end of the file, the run is shorter in the file
+		if run_content().map(|bytes| bytes.len() as u64).sum::<u64>() > end - start {
+			return;
+		}
+		let content: Vec<u8> = run_content().flatten().copied().collect();
+
+		if self.read_file_bytes(start, (end - start) as usize).ok() == Some(content) {
+			for address in start..end {
+				self.modified_bytes.remove(&address);
+			}
+		}
+	}
+
+	/// Clean the vectors found on both sides of the deleted bytes around `file_address`
+	fn clean_around_deleted_bytes(&mut self, file_address: u64) {
+		if self.modified_bytes.get(&file_address) != Some(&Changes::Deleted) {
+			return;
+		}
+
+		let before = self.modified_bytes.range(..file_address).rev()
+			.take(MAX_CLEANING_DISTANCE as usize)
+			.find(|(_, changes)| **changes != Changes::Deleted)
+			.map(|(address, _)| *address);
+		let after = self.modified_bytes.range(file_address..)
+			.take(MAX_CLEANING_DISTANCE as usize)
+			.find(|(_, changes)| **changes != Changes::Deleted)
+			.map(|(address, _)| *address);
+
+		for address in before.into_iter().chain(after) {
+			self.clean_modified_bytes(address);
+		}
+	}
+
+	/// read up to `len` bytes at the address `address` of `self.file`, ignoring the modifications.
+	/// Less bytes are returned at the end of the file
+	fn read_file_bytes(&mut self, address: u64, len: usize) -> Result<Vec<u8>, std::io::Error> {
+		self.reader.seek(SeekFrom::Start(address))?;
+
+		let mut buf = Vec::with_capacity(len);
+		(&mut self.reader).take(len as u64).read_to_end(&mut buf)?;
+		Ok(buf)
+	}
+
+	/// Read the bytes from `start` to `end` (excluded), including the unsaved changes.
+	/// The bytes are given to `callback` in several chunks, so big files are not loaded in memory
+	pub fn read_edited_range(&mut self, start: u64, end: u64, callback: impl FnMut(&[u8])) -> Result<(), std::io::Error> {
+		let end = min(end, self.file_size);
+
+		if end <= start {
+			return Ok(());
+		}
+
+		let real_address = self.get_real_address(start);
+		self.read_real_range(real_address, end - start, callback)
+	}
+
+	/// Same as read_edited_range(), for the `len` bytes from `real_address`, given by get_real_address()
+	fn read_real_range(&mut self, real_address: Addr, len: u64, mut callback: impl FnMut(&[u8])) -> Result<(), std::io::Error> {
+		const CHUNK_SIZE: u64 = 0x10000;
+
+		let mut remaining = len;
+
+		let (mut file_address, mut offset_in_vector) = match real_address {
+			Addr::FileAddress(address) => (address, 0),
+			Addr::InsertedAddress(Inserted { vector_address, offset_in_vector }) => (vector_address, offset_in_vector as usize)
+		};
+
+		while remaining > 0 {
+			let next_modified_address = self.modified_bytes.range(file_address..).next().map(|(address, _)| *address);
+
+			// bytes modified: give the inserted bytes, or skip the deleted byte
+			if next_modified_address == Some(file_address) {
+				if let Some(Changes::Insertion(inserted_vec)) = self.modified_bytes.get(&file_address) {
+					let len = min(inserted_vec.len() - offset_in_vector, remaining as usize);
+					callback(&inserted_vec[offset_in_vector..offset_in_vector + len]);
+					remaining -= len as u64;
 				}
+
+				file_address += 1;
+				offset_in_vector = 0;
+				continue;
+			}
+
+			// bytes of the file, until the next modification
+			let len = match next_modified_address {
+				Some(address) => min(address - file_address, remaining),
+				None => remaining
 			};
 
-			match inserted_vec.len() {
-				1 => {
-					let byte_addr = modified_addr.checked_add_signed(real_addr_delta).unwrap_or_default();
-					if let Ok(file_byteval) = self.read_byte_addr_file(byte_addr) {
-						if inserted_vec.iter().nth(0) == Some(&file_byteval) {
-							addrs_to_remove.push(modified_addr);
-						}
-					}
-				},
-				_ => {
-					real_addr_delta += inserted_vec.len() as i64 - 1
-				}
+			let file_bytes = self.read_file_bytes(file_address, min(len, CHUNK_SIZE) as usize)?;
+			if file_bytes.is_empty() {
+				return Err(Error::new(ErrorKind::UnexpectedEof, "Unexpected end of file"));
 			}
-		}
 
-		for addr in addrs_to_remove {
-			self.modified_bytes.remove(&addr);
+			callback(&file_bytes);
+			remaining -= file_bytes.len() as u64;
+			file_address += file_bytes.len() as u64;
 		}
+
+		Ok(())
 	}
 
 	/// read a single byte (u8) at the address `address`, from `self.reader`
 	/// if the byte has been modified, give the value from `self.modified`
 	pub fn read_byte_addr(&mut self, address: u64) -> Result<u8, std::io::Error> {
+		let real_address = self.get_real_address(address);
+		self.read_real_address(real_address)
+	}
+
+	/// read the byte at `real_address`, given by `get_real_address()`
+	fn read_real_address(&mut self, real_address: Addr) -> Result<u8, std::io::Error> {
 
-		match self.get_real_address(address) {
+		match real_address {
 
 			// value is in modified_bytes
 			Addr::InsertedAddress(Inserted {vector_address, offset_in_vector}) => {
@@ -336,6 +849,40 @@ impl App {
 		}
 	}
 
+	/// Give the real address of the byte following `real_address`.
+	/// Unlike get_real_address(), we only look at the modified_bytes we step over
+	fn next_real_address(&self, real_address: Addr) -> Addr {
+		let (mut key, mut offset) = match real_address {
+			Addr::FileAddress(addr) => (addr, 0),
+			Addr::InsertedAddress(Inserted{vector_address, offset_in_vector}) => (vector_address, offset_in_vector)
+		};
+
+		offset += 1;
+
+		// deleted bytes take no place, a vector of inserted bytes takes its length
+		loop {
+			let len = match self.modified_bytes.get(&key) {
+				None => 1,
+				Some(Changes::Deleted) => 0,
+				Some(Changes::Insertion(inserted_vec)) => inserted_vec.len() as u64
+			};
+
+			if offset < len {
+				break;
+			}
+			key += 1;
+			offset = 0;
+		}
+
+		match self.modified_bytes.get(&key) {
+			Some(Changes::Insertion(_)) => Addr::InsertedAddress(Inserted {
+				vector_address: key,
+				offset_in_vector: offset
+			}),
+			_ => Addr::FileAddress(key)
+		}
+	}
+
 	/// read a single byte (u8) at the address `address`, from `self.reader`
 	/// Even if the byte has been modified, give the value from `self.reader`
 	pub fn read_byte_addr_file(&mut self, address: u64) -> Result<u8, std::io::Error> {
@@ -349,8 +896,76 @@ impl App {
 		Ok(value)
 	}
 
+	/// Overwrite the byte at `real_address`, given by `get_real_address()`.
+	/// This doesn't move the other bytes. Return the key of self.modified_bytes changed,
+	/// the caller has to clean it with clean_modified_bytes()
+	fn overwrite_real_address(&mut self, real_address: Addr, value: u8) -> u64 {
+		let (insertion_address, offset_in_vector) = match real_address {
+			Addr::FileAddress(addr) => (addr, 0),
+			Addr::InsertedAddress(Inserted{vector_address, offset_in_vector}) => (vector_address, offset_in_vector)
+		};
+
+		match self.modified_bytes.get_mut(&insertion_address) {
+			
+			// There is no bytes for the moment, we create a Changes::Insertion byte with
+			// the new value
+			None => {
+				let changes = Changes::Insertion(vec![value]);
+				self.modified_bytes.insert(insertion_address, changes);
+			},
+
+			// They are modified bytes, we overwrite the modified byte with a new value
+			Some(changes) => {
+				match changes {
+					Changes::Insertion(inserted_values) => {
+						inserted_values[offset_in_vector as usize] = value;
+					},
+					Changes::Deleted => { 
+						panic!("Should we be able change a delete byte in overwrite ?");
+						// *changes = Changes::Insertion(vec![value]);
+						// Ok(())
+					}
+				}
+			}
+		}
+
+		insertion_address
+	}
+
+	/// Count a change of the bytes from `start` to `end` (excluded), so that the caches are computed again
+	fn bytes_changed(&mut self, start: u64, end: u64) {
+		self.changes_count += 1;
+
+		if let Some(file_map) = &mut self.file_map {
+			file_map.edit(start, end);
+		}
+	}
+
+	/// Count the insertion of `count` bytes at `address`: the bytes and the marks after it are shifted
+	fn bytes_inserted(&mut self, address: u64, count: u64) {
+		self.bytes_changed(address, u64::MAX);
+
+		for mark in self.marks.values_mut().filter(|mark| mark.address >= address) {
+			mark.address += count;
+		}
+	}
+
+	/// Count the removal of `count` bytes from `address`. The marks on the bytes removed
+	/// go to the byte after them
+	fn bytes_removed(&mut self, address: u64, count: u64) {
+		self.bytes_changed(address, u64::MAX);
+
+		for mark in self.marks.values_mut().filter(|mark| mark.address > address) {
+			mark.address = max(mark.address.saturating_sub(count), address);
+		}
+	}
+
 	/// write a single byte (u8), at the address `address`
 	pub fn write_byte(&mut self, address: u64, value: u8, mode: Mode) -> Result<(), std::io::Error> {
+		match mode {
+			Mode::Insert => self.bytes_inserted(address, 1),
+			_ => self.bytes_changed(address, address + 1)
+		}
 
 		// We overwrite the current byte, modification is stored inside `app.modified_bytes`
 		if mode == Mode::Overwrite {
@@ -362,34 +977,11 @@ impl App {
 				}
 			}
 
-			let (insertion_address, offset_in_vector) = match self.get_real_address(address) {
-				Addr::FileAddress(addr) => (addr, 0),
-				Addr::InsertedAddress(Inserted{vector_address, offset_in_vector}) => (vector_address, offset_in_vector)
-			};
-
-			match self.modified_bytes.get_mut(&insertion_address) {
-				
-				// There is no bytes for the moment, we create a Changes::Insertion byte with
-				// the new value
-				None => {
-					let changes = Changes::Insertion(vec![value]);
-					self.modified_bytes.insert(insertion_address, changes);
-				},
+			let real_address = self.get_real_address(address);
+			let insertion_address = self.overwrite_real_address(real_address, value);
 
-				// They are modified bytes, we overwrite the modified byte with a new value
-				Some(changes) => {
-					match changes {
-						Changes::Insertion(inserted_values) => {
-							inserted_values[offset_in_vector as usize] = value;
-						},
-						Changes::Deleted => { 
-							panic!("Should we be able change a delete byte in overwrite ?");
-							// *changes = Changes::Insertion(vec![value]);
-							// Ok(())
-						}
-					}
-				}
-			}
+			// Remove stored modifications that aren't real changes
+			self.clean_modified_bytes(insertion_address);
 
 		// Insertion mode
 		} else if mode == Mode::Insert {
@@ -399,19 +991,32 @@ impl App {
 				Addr::InsertedAddress(Inserted{vector_address, offset_in_vector}) => (vector_address, offset_in_vector)
 			};
 
-			match self.modified_bytes.get_mut(&insertion_address) {
-				// We append to the end of a file, on in an empty file.
-				None if insertion_address >= self.file_size => {
-					let inserted_bytes = vec![value];
-					self.modified_bytes.insert(insertion_address, Changes::Insertion(inserted_bytes));
-				},
+			// If the file bytes just before have been modified or deleted, we store our byte with these changes.
+			// This way, undoing a deletion restores the original bytes instead of creating a new insertion.
+			let previous_address = match offset_in_vector {
+				0 => self.previous_modified_address(insertion_address, value),
+				_ => None
+			};
+
+			if let Some(previous_address) = previous_address {
+				match self.modified_bytes.get_mut(&previous_address) {
+					Some(Changes::Insertion(inserted_bytes)) => inserted_bytes.push(value),
+					_ => { self.modified_bytes.insert(previous_address, Changes::Insertion(vec![value])); }
+				}
+
+				self.file_size += 1;
+				self.clean_modified_bytes(previous_address);
+				return Ok(());
+			}
 
-				// If there are no inserted bytes, we create a vector with the current value, our new value
+			match self.modified_bytes.get_mut(&insertion_address) {
+				// If there are no inserted bytes, we create a vector with our new value, and the current value
 				// and we add it to the modified_bytes structure.
 				None => {
-					let current_val = self.read_byte_addr_file(insertion_address)
-						.unwrap_or(0x00); // add a default value so that we can insert bytes in an empty file
-					let inserted_bytes = vec![value, current_val];
+					let inserted_bytes = match self.read_byte_addr_file(insertion_address) {
+						Ok(current_val) => vec![value, current_val],
+						Err(_) => vec![value] // We append to the end of a file, or in an empty file.
+					};
 					self.modified_bytes.insert(insertion_address, Changes::Insertion(inserted_bytes));
 				},
 				Some(changes) => {
@@ -428,18 +1033,60 @@ impl App {
 
 			// We have inserted a new byte, let's update file_size
 			self.file_size = self.file_size + 1;
+
+			// Remove stored modifications that aren't real changes
+			self.clean_modified_bytes(insertion_address);
 		} else {
 			panic!("Only Insert and Overwrite were implemented for write_byte");
 		}
 
-		// Remove stored modifications that aren't real changes
-		self.clean_modified_bytes();
-
 		Ok(())
 	}
 
+	/// Find the entry of self.modified_bytes just before the file address `file_address`,
+	/// where we can store a byte inserted before `file_address`.
+	/// If the bytes before are deleted, we prefer a deleted byte that had the same `value`.
+	fn previous_modified_address(&mut self, file_address: u64, value: u8) -> Option<u64> {
+		let previous_address = file_address.checked_sub(1)?;
+
+		if let Changes::Insertion(_) = self.modified_bytes.get(&previous_address)? {
+			return Some(previous_address);
+		}
+
+		// Undoing the deletion of consecutive bytes restores them either at the start or
+		// at the end of the deleted bytes. This walk is not slower than get_real_address()
+		let first_deleted_address = self.modified_bytes.range(..file_address).rev()
+			.zip((0..file_address).rev())
+			.take_while(|((address, changes), expected)| *address == expected && **changes == Changes::Deleted)
+			.last()
+			.map(|((address, _), _)| *address)?;
+
+		if self.read_byte_addr_file(first_deleted_address).ok() == Some(value) {
+			return Some(first_deleted_address);
+		}
+
+		// Search a deleted byte with the same value. Don't search too far, this is only
+		// used to keep self.modified_bytes clean
+		let mut address = previous_address;
+
+		for _ in 0..MAX_CLEANING_DISTANCE {
+			if self.read_byte_addr_file(address).ok() == Some(value) {
+				return Some(address);
+			}
+
+			match address.checked_sub(1) {
+				Some(addr) if self.modified_bytes.get(&addr) == Some(&Changes::Deleted) => address = addr,
+				_ => break
+			}
+		}
+
+		Some(previous_address)
+	}
+
+	/// write the digit `value` at the cursor, in the base of the data pane
 	pub fn write(&mut self, cursor: u64, value: u8) {
-		let address = cursor / 2; // use this to point at the edited byte
+		let address = cursor / self.digits(); // use this to point at the edited byte
+		let position = cursor % self.digits(); // digit of the byte edited
 
 		if self.mode == Mode::Overwrite {
 			// return if we don't have any bytes we can overwrite
@@ -448,21 +1095,15 @@ impl App {
 				return;
 			}
 
-			self.add_to_history(Modification::Modification, address);
-
 			let original_value = self.read_byte_addr(address).expect("Failed to write byte");
 	
-			// Determine if we write the first or second letter of the byte
-			let mut new_value: u8;
-	
-			if cursor % 2 == 0 { // we edit the first char of the hex
-				new_value = original_value & 0b1111;
-				new_value = new_value ^ (value << 4);
-			} 
-			else { // we edit the second char of the hex
-				new_value = original_value & 0b11110000;
-				new_value = new_value ^ value;
-			}
+			// Replace the digit of the byte, i.e the first or second letter in hex
+			let Some(new_value) = self.view.replace_digit(original_value, position, value) else {
+				self.add_error_message(WarningLevel::Warning, String::from("The value doesn't fit in a byte"));
+				return;
+			};
+
+			self.add_to_history(Modification::Modification, address);
 	
 			// Write the byte
 			self.write_byte(address, new_value, Mode::Overwrite)
@@ -470,20 +1111,27 @@ impl App {
 		
 		} else if self.mode == Mode::Insert {
 			
-			if cursor % 2 == 0 { // we edit the first char of the hex
+			if position == 0 { // we edit the first char of the byte
+				let Some(value) = self.view.replace_digit(0, 0, value) else {
+					self.add_error_message(WarningLevel::Warning, String::from("The value doesn't fit in a byte"));
+					return;
+				};
+
 				self.add_to_history(Modification::Insertion, address);
 				
-				let value = value << 4;
 				self.write_byte(address, value, Mode::Insert)
 				.expect("Failed to insert byte");
 			
 
-			} else { // we edit the second char of the hex -> Overwrite instead of Insterting
-				self.add_to_history(Modification::Modification, address);
-				
+			} else { // we edit the other chars of the byte -> Overwrite instead of Insterting
 				let original_value = self.read_byte_addr(address).expect("Failed to write byte");
 
-				let new_value = (original_value & 0b11110000) ^ value;
+				let Some(new_value) = self.view.replace_digit(original_value, position, value) else {
+					self.add_error_message(WarningLevel::Warning, String::from("The value doesn't fit in a byte"));
+					return;
+				};
+
+				self.add_to_history(Modification::Modification, address);
 
 				self.write_byte(address, new_value, Mode::Overwrite)
 					.expect("Failed to overwrite the 2nd char of byte");
@@ -493,16 +1141,79 @@ impl App {
 		else { panic!("Only Mode::Overwrite and Mode::Insert were considered")}
 
 		// empty self.history_redo
-		if self.history_redo.len() > 0 {
-			self.history_redo = vec![];
-		}
+		self.clear_redo();
+
+		self.reset();
+	}
+
+	/// Toggle the bit under the cursor, in the binary view
+	pub fn toggle_bit(&mut self) {
+		let address = self.cursor / self.digits();
+		let bit = 7 - self.cursor % self.digits();
+
+		let Ok(original_value) = self.read_byte_addr(address) else {
+			self.add_error_message(WarningLevel::Info, String::from("No bit to toggle"));
+			return;
+		};
+
+		// the bit is toggled in place, even in insert mode
+		self.add_to_history(Modification::Modification, address);
+		self.write_byte(address, original_value ^ (1 << bit), Mode::Overwrite)
+			.expect("Failed to write byte");
+
+		// empty self.history_redo
+		self.clear_redo();
 
 		self.reset();
 	}
 
-	/// write a byte at the address given
-	pub fn write_ascii(&mut self, cursor: u64, value: u8) {
-		let address = cursor / 2; // use this to point at the edited byte
+	/// At most `len` bytes from the byte pointed by the cursor, less at the end of the file
+	pub fn cursor_bytes(&mut self, len: u64) -> Vec<u8> {
+		let address = self.cursor / self.digits();
+		let mut bytes: Vec<u8> = vec![];
+
+		let _ = self.read_edited_range(address, address + len, |chunk| bytes.extend_from_slice(chunk));
+		bytes
+	}
+
+	/// Glyphs of the text pane for `bytes`, read at `address`. The bytes around are read
+	/// to decode the characters that overlap the start or the end of `bytes`
+	pub fn decode_text(&mut self, address: u64, bytes: &[u8]) -> Vec<Glyph> {
+		let context = self.encoding.context();
+		let mut around: Vec<u8> = vec![];
+
+		let start = address.saturating_sub(context);
+		let _ = self.read_edited_range(start, address, |chunk| around.extend_from_slice(chunk));
+		let first = around.len();
+
+		around.extend_from_slice(bytes);
+
+		let end = address + bytes.len() as u64;
+		let _ = self.read_edited_range(end, end + context, |chunk| around.extend_from_slice(chunk));
+
+		self.encoding.decode(&around, address - first as u64, first..first + bytes.len())
+	}
+
+	/// Write the char `c` at the cursor, encoded with `self.encoding`. Return the number of bytes written
+	pub fn write_ascii(&mut self, cursor: u64, c: char) -> u64 {
+		let address = cursor / self.digits(); // use this to point at the edited byte
+
+		let bytes = match self.encoding.encode(c) {
+			Some(bytes) => bytes,
+			None => {
+				self.add_error_message(
+					WarningLevel::Warning,
+					format!("'{}' can't be encoded in {}", c, self.encoding.name())
+				);
+				return 0;
+			}
+		};
+
+		// the chars of several bytes are a single step for undo()
+		let value = match bytes[..] {
+			[value] => value,
+			_ => return self.write_text(address, &bytes)
+		};
 
 		// Add the current value to history
 		match self.mode {
@@ -519,11 +1230,35 @@ impl App {
 				)});
 
 		// empty self.history_redo
-		if self.history_redo.len() > 0 {
-			self.history_redo = vec![];
+		self.clear_redo();
+
+		self.reset();
+		1
+	}
+
+	/// Write the bytes of a char typed in the text pane at `address`, as a single step for undo().
+	/// In overwrite mode, the bytes after the end of the file are appended. Return the number of bytes written
+	fn write_text(&mut self, address: u64, bytes: &[u8]) -> u64 {
+		self.start_transaction(address);
+
+		let overwritten = match self.mode {
+			Mode::Insert => 0,
+			Mode::Overwrite => min(bytes.len() as u64, self.file_size.saturating_sub(address)) as usize
+		};
+
+		for (i, value) in bytes[..overwritten].iter().enumerate() {
+			let byte_address = address + i as u64;
+
+			self.add_to_history(Modification::Modification, byte_address);
+			self.write_byte(byte_address, *value, Mode::Overwrite).expect("Failed to write byte");
 		}
 
+		self.insert_bytes(address + overwritten as u64, &bytes[overwritten..]);
+
+		self.end_transaction(address);
 		self.reset();
+
+		bytes.len() as u64
 	}
 
 	/// private function to delete a byte. Don't add the value to `self.history` use `delete_byte()` instead
@@ -536,6 +1271,8 @@ impl App {
 			return;
 		}
 
+		self.bytes_removed(address, 1);
+
 		let real_address = self.get_real_address(address);
 
 		match real_address {
@@ -563,9 +1300,23 @@ impl App {
 							}
 						}
 					}
+
+					// Remove the vector if we are back to the original file byte
+					self.clean_modified_bytes(vector_address);
+
+					// Bytes appended after the end of the file don't replace any byte of the file,
+					// there is no file byte to mark as deleted
+					if self.modified_bytes.get(&vector_address) == Some(&Changes::Deleted)
+						&& self.read_byte_addr_file(vector_address).is_err() {
+						self.modified_bytes.remove(&vector_address);
+					}
 			}
 		}
 
+		// A new deleted byte can allow the vectors around to give back their bytes
+		let (Addr::FileAddress(deleted_address) | Addr::InsertedAddress(Inserted { vector_address: deleted_address, .. })) = real_address;
+		self.clean_around_deleted_bytes(deleted_address);
+
 		self.file_size -= 1;
 	}
 
@@ -588,6 +1339,332 @@ impl App {
 		self.remove_byte(address);
 	}
 
+	/// Delete `len` bytes starting at `address`. The bytes are added to `self.history` as a single block
+	fn delete_bytes(&mut self, address: u64, len: u64) {
+		let len = min(len, self.file_size.saturating_sub(address));
+		if len == 0 {
+			return;
+		}
+
+		let mut bytes = Vec::with_capacity(len as usize);
+		if let Err(e) = self.read_edited_range(address, address + len, |chunk| bytes.extend_from_slice(chunk)) {
+			self.add_error_message(WarningLevel::Error, format!("Failed to read the bytes to delete: {}", e));
+			return;
+		}
+
+		self.remove_range(address, len);
+
+		self.history.push((Modification::DeletedBlock, address, None));
+		self.history_blocks.push(bytes);
+	}
+
+	/// Insert `bytes` before the byte at `address`. The bytes are added to `self.history` as a single block
+	fn insert_bytes(&mut self, address: u64, bytes: &[u8]) {
+		if bytes.is_empty() {
+			return;
+		}
+
+		if self.insert_range(address, bytes) {
+			self.history.push((Modification::InsertedBlock, address, None));
+			self.history_blocks.push(bytes.to_vec());
+		}
+	}
+
+	/// Remove the `len` bytes starting at `address`, without adding them to the history.
+	/// self.modified_bytes is walked once for the whole range, instead of once per byte
+	fn remove_range(&mut self, address: u64, len: u64) {
+		let len = min(len, self.file_size.saturating_sub(address));
+		if len == 0 {
+			return;
+		}
+
+		self.bytes_removed(address, len);
+
+		let (mut key, mut offset) = match self.get_real_address(address) {
+			Addr::FileAddress(addr) => (addr, 0),
+			Addr::InsertedAddress(Inserted { vector_address, offset_in_vector }) => (vector_address, offset_in_vector)
+		};
+		let first_key = key;
+		let mut last_key = key;
+		let mut remaining = len;
+
+		while remaining > 0 {
+			last_key = key;
+
+			match self.modified_bytes.get_mut(&key) {
+				// remove the bytes from the vector of inserted bytes
+				Some(Changes::Insertion(inserted_vec)) => {
+					let removed = min(remaining, inserted_vec.len() as u64 - offset);
+					inserted_vec.drain(offset as usize..(offset + removed) as usize);
+					remaining -= removed;
+
+					// Bytes appended after the end of the file don't replace any byte of the file,
+					// there is no file byte to mark as deleted
+					if inserted_vec.is_empty() {
+						match self.read_byte_addr_file(key).is_ok() {
+							true => { self.modified_bytes.insert(key, Changes::Deleted); },
+							false => { self.modified_bytes.remove(&key); }
+						}
+					}
+				},
+				Some(Changes::Deleted) => {},
+
+				// bytes of the file, until the next change
+				None => {
+					let next_modified_address = self.modified_bytes.range(key..).next().map(|(address, _)| *address);
+					let removed = match next_modified_address {
+						Some(next_address) => min(remaining, next_address - key),
+						None => remaining
+					};
+
+					for file_address in key..key + removed {
+						self.modified_bytes.insert(file_address, Changes::Deleted);
+					}
+					remaining -= removed;
+					last_key = key + removed - 1;
+					key += removed;
+					offset = 0;
+					continue;
+				}
+			}
+
+			key += 1;
+			offset = 0;
+		}
+
+		self.file_size -= len;
+
+		// Remove the vectors back to the file bytes, and let the vectors around
+		// the deleted bytes give back their bytes, once for the whole range
+		for key in [first_key, last_key] {
+			self.clean_modified_bytes(key);
+			self.clean_around_deleted_bytes(key);
+		}
+	}
+
+	/// Insert `bytes` before the byte at `address`, without adding them to the history.
+	/// Return false if they can't be inserted
+	fn insert_range(&mut self, address: u64, bytes: &[u8]) -> bool {
+		let mut address = address;
+		let mut bytes = bytes;
+
+		while let Some((first_byte, other_bytes)) = bytes.split_first() {
+			if self.write_byte(address, *first_byte, Mode::Insert).is_err() {
+				self.add_error_message(
+					WarningLevel::Error,
+					format!("Failed to insert a byte at address 0x{:x}", address)
+				);
+				return false;
+			}
+
+			match self.get_real_address(address) {
+				// The first byte decides in which vector of inserted bytes the bytes go.
+				// Add the other bytes after it at once, instead of shifting the vector for each byte
+				Addr::InsertedAddress(Inserted { vector_address, offset_in_vector }) => {
+					if let Some(Changes::Insertion(inserted_vec)) = self.modified_bytes.get_mut(&vector_address) {
+						let offset = offset_in_vector as usize + 1;
+						inserted_vec.splice(offset..offset, other_bytes.iter().copied());
+
+						self.bytes_inserted(address + 1, other_bytes.len() as u64);
+						self.file_size += other_bytes.len() as u64;
+						self.clean_modified_bytes(vector_address);
+					}
+					return true;
+				},
+
+				// The first byte went back to a deleted byte of the file, e.g. when undoing a deletion.
+				// The next bytes can go back to the deleted bytes after it
+				Addr::FileAddress(file_address) => {
+					let restored = self.restore_deleted_run(file_address + 1, other_bytes);
+					self.bytes_inserted(address + 1, restored as u64);
+					address += 1 + restored as u64;
+					bytes = &other_bytes[restored..];
+				}
+			}
+		}
+
+		true
+	}
+
+	/// Restore the consecutive deleted bytes of the file from `file_address`, as long as they
+	/// have the values of `bytes`. Return the number of bytes restored, counted by the caller
+	fn restore_deleted_run(&mut self, file_address: u64, bytes: &[u8]) -> usize {
+		let deleted = self.modified_bytes.range(file_address..)
+			.zip(file_address..)
+			.take(bytes.len())
+			.take_while(|((address, changes), expected)| *address == expected && **changes == Changes::Deleted)
+			.count();
+
+		let file_bytes = self.read_file_bytes(file_address, deleted).unwrap_or_default();
+		let restored = file_bytes.iter().zip(bytes).take_while(|(file_byte, byte)| file_byte == byte).count();
+
+		if restored == 0 {
+			return 0;
+		}
+
+		for address in file_address..file_address + restored as u64 {
+			self.modified_bytes.remove(&address);
+		}
+		self.file_size += restored as u64;
+
+		restored
+	}
+
+	/// Insert `count` bytes filled with `pattern` at the cursor, whatever the current mode.
+	/// This is a single step for undo()
+	pub fn insert_pattern(&mut self, count: u64, pattern: &[u8]) {
+		if count == 0 {
+			self.add_error_message(WarningLevel::Info, "No bytes to insert".to_string());
+			return;
+		}
+
+		if count > MAX_RESIZED_BYTES {
+			self.add_error_message(
+				WarningLevel::Error,
+				format!("Can't insert more than 0x{:x} bytes at once", MAX_RESIZED_BYTES)
+			);
+			return;
+		}
+
+		let address = self.cursor / self.digits();
+
+		self.start_transaction(address);
+		self.insert_bytes(address, &fill_pattern(pattern, count as usize));
+		self.end_transaction(address);
+		self.reset();
+
+		self.add_error_message(
+			WarningLevel::Info,
+			format!("Inserted 0x{:x} bytes at 0x{:x}", count, self.display_address(address))
+		);
+	}
+
+	/// Delete all the selected bytes, as a single step for undo().
+	/// Return the bytes deleted
+	pub fn delete_selection(&mut self) -> Option<Vec<u8>> {
+		let (start, end) = self.selection_range()?;
+		let selected_bytes = self.get_selected_bytes()?;
+
+		self.start_transaction(start);
+		self.delete_bytes(start, end - start);
+		self.end_transaction(start);
+
+		self.selection_start = None;
+		self.cursor_jump_to(start * self.digits());
+		self.reset();
+
+		Some(selected_bytes)
+	}
+
+	/// Insert `bytes` at the cursor position, as a single step for undo()
+	pub fn paste(&mut self, bytes: &[u8]) {
+		let address = self.cursor / self.digits();
+
+		match self.mode {
+			Mode::Insert => {
+				self.start_transaction(address);
+				self.insert_bytes(address, bytes);
+				self.end_transaction(address);
+				self.reset();
+			},
+			Mode::Overwrite => {
+				// don't write after the end of the file
+				let len = min(bytes.len() as u64, self.file_size.saturating_sub(address)) as usize;
+				self.overwrite_bytes(address, &bytes[..len]);
+			}
+		}
+	}
+
+	/// Move the selected bytes before the byte at `destination`, as a single step for undo()
+	pub fn move_block(&mut self, destination: u64) {
+		let (start, end) = match self.selection_range() {
+			Some(range) => range,
+			None => {
+				self.add_error_message(WarningLevel::Info, "Select the bytes to move first".to_string());
+				return;
+			}
+		};
+
+		if destination > self.file_size {
+			self.add_error_message(
+				WarningLevel::Warning,
+				format!("0x{:x} is after the end of the file", self.display_address(destination))
+			);
+			return;
+		}
+
+		if start < destination && destination < end {
+			self.add_error_message(WarningLevel::Warning, "Can't move the selection inside itself".to_string());
+			return;
+		}
+
+		let selected_bytes = self.get_selected_bytes().unwrap_or_default();
+		let len = end - start;
+
+		// The bytes after the selection are shifted once the selection is removed
+		let destination = match destination >= end {
+			true  => destination - len,
+			false => destination
+		};
+
+		self.start_transaction(start);
+		self.delete_bytes(start, len);
+		self.insert_bytes(destination, &selected_bytes);
+		self.end_transaction(start);
+
+		// select the bytes at their new location
+		self.selection_start = Some(destination * self.digits());
+		self.cursor_jump_to(((destination + len) * self.digits()).saturating_sub(1));
+		self.reset();
+
+		self.add_error_message(
+			WarningLevel::Info,
+			format!("Moved 0x{:x} bytes to 0x{:x}", len, self.display_address(destination))
+		);
+	}
+
+	/// Truncate or extend the file to `new_size` bytes, as a single step for undo().
+	/// The bytes appended are filled with `pattern`
+	pub fn resize(&mut self, new_size: u64, pattern: &[u8]) {
+		let old_size = self.file_size;
+
+		if new_size == old_size {
+			self.add_error_message(WarningLevel::Info, format!("The file is already 0x{:x} bytes long", old_size));
+			return;
+		}
+
+		if new_size.abs_diff(old_size) > MAX_RESIZED_BYTES {
+			self.add_error_message(
+				WarningLevel::Warning,
+				format!("Can't add or remove more than 0x{:x} bytes at once", MAX_RESIZED_BYTES)
+			);
+			return;
+		}
+
+		let address = min(old_size, new_size);
+		self.start_transaction(address);
+
+		if new_size < old_size {
+			self.delete_bytes(new_size, old_size - new_size);
+		} else {
+			self.insert_bytes(old_size, &fill_pattern(pattern, (new_size - old_size) as usize));
+		}
+
+		self.end_transaction(address);
+
+		// the cursor may be after the new end of the file
+		if self.cursor / self.digits() >= new_size {
+			self.selection_start = None;
+			self.cursor_jump_to(new_size.saturating_sub(1) * self.digits());
+		}
+		self.reset();
+
+		self.add_error_message(
+			WarningLevel::Info,
+			format!("Resized the file from 0x{:x} to 0x{:x} bytes", old_size, self.file_size)
+		);
+	}
+
 	/// add the Modification of `address` to `self.history`
 	fn add_to_history(&mut self, modif: Modification, address: u64) {
 
@@ -613,6 +1690,151 @@ impl App {
 						format!("Could not backup byte at address 0x{:x}: {}", address, e)
 					)
 				}
+			},
+			Modification::TransactionStart | Modification::TransactionEnd => {
+				self.history.push((modif, address, None));
+			},
+			Modification::InsertedBlock | Modification::DeletedBlock => {
+				panic!("The blocks are added to the history with their bytes, by insert_bytes() and delete_bytes()");
+			}
+		}
+	}
+
+	/// Start a transaction: all the changes added to `self.history` until `end_transaction()`
+	/// is called will be undone / redone in a single step
+	fn start_transaction(&mut self, address: u64) {
+		self.add_to_history(Modification::TransactionStart, address);
+	}
+
+	/// Forget the changes that could be redone, after a new change
+	fn clear_redo(&mut self) {
+		self.history_redo.clear();
+		self.history_redo_blocks.clear();
+	}
+
+	/// Close the transaction opened by `start_transaction()`
+	fn end_transaction(&mut self, address: u64) {
+		// don't keep empty transactions in the history
+		if let Some((Modification::TransactionStart, _, _)) = self.history.last() {
+			self.history.pop();
+			return;
+		}
+
+		self.add_to_history(Modification::TransactionEnd, address);
+
+		// empty self.history_redo
+		self.clear_redo();
+	}
+
+	/// Overwrite the bytes starting at `address` with `bytes`.
+	/// All the changes are a single step for undo(). Return the number of bytes changed
+	pub fn overwrite_bytes(&mut self, address: u64, bytes: &[u8]) -> usize {
+		self.start_transaction(address);
+
+		let previous_values = self.overwrite_range(address, bytes);
+
+		for (byte_address, previous_value) in &previous_values {
+			self.history.push((Modification::Modification, *byte_address, Some(*previous_value)));
+		}
+
+		self.end_transaction(address);
+		self.reset();
+
+		previous_values.len()
+	}
+
+	/// Overwrite the bytes starting at `address` with `bytes`, without adding them to the history.
+	/// Return the address and the previous value of the bytes changed
+	fn overwrite_range(&mut self, address: u64, bytes: &[u8]) -> Vec<(u64, u8)> {
+		let mut previous_values = vec![];
+
+		// get_real_address() walks self.modified_bytes from the start, so we only use it
+		// for the first byte. Overwriting doesn't move the bytes that follow.
+		let mut real_address = self.get_real_address(address);
+		let mut overwritten_keys = None;
+
+		for (i, value) in bytes.iter().enumerate() {
+			let byte_address = address + i as u64;
+
+			if i > 0 {
+				real_address = self.next_real_address(real_address);
+			}
+
+			// don't keep the bytes that aren't changed
+			let current_value = match self.read_real_address(real_address) {
+				Ok(current_value) if current_value == *value => continue,
+				Ok(current_value) => current_value,
+				Err(_e) => {
+					self.add_error_message(
+						WarningLevel::Error,
+						format!("No byte to overwrite at 0x{:x}", byte_address)
+					);
+					break;
+				}
+			};
+
+			self.bytes_changed(byte_address, byte_address + 1);
+			let key = self.overwrite_real_address(real_address, *value);
+			previous_values.push((byte_address, current_value));
+
+			overwritten_keys = match overwritten_keys {
+				None => Some((key, key)),
+				Some((first_key, _)) => Some((first_key, key))
+			};
+		}
+
+		// Cleaning after each byte is slow, clean all the bytes at once
+		if let Some((first_key, last_key)) = overwritten_keys {
+			self.clean_overwritten_bytes(first_key, last_key);
+		}
+
+		previous_values
+	}
+
+	/// Undo (or redo) at once the Modification of consecutive bytes at the end of self.history
+	/// (or self.history_redo), like the ones added by overwrite_bytes().
+	/// This is much faster than restoring the bytes one by one with write_byte()
+	fn restore_overwrites(&mut self, redo: bool) {
+		let history = match redo {
+			true  => &mut self.history_redo,
+			false => &mut self.history
+		};
+
+		// undo goes backward through the bytes, redo goes forward
+		let mut values: Vec<(u64, u8)> = vec![];
+
+		while let Some((Modification::Modification, address, Some(value))) = history.last().copied() {
+			if let Some((last_address, _)) = values.last() {
+				let next_address = match redo {
+					true  => last_address.checked_add(1),
+					false => last_address.checked_sub(1)
+				};
+				if next_address != Some(address) {
+					break;
+				}
+			}
+
+			history.pop();
+			values.push((address, value));
+		}
+
+		if !redo {
+			values.reverse();
+		}
+
+		let Some((address, _)) = values.first().copied() else { return };
+		let bytes: Vec<u8> = values.iter().map(|(_, value)| *value).collect();
+
+		let previous_values = self.overwrite_range(address, &bytes);
+
+		// same order as undo() and redo() would have used
+		if redo {
+			for (byte_address, previous_value) in previous_values {
+				self.history.push((Modification::Modification, byte_address, Some(previous_value)));
+			}
+		} else {
+			for (byte_address, previous_value) in previous_values.into_iter().rev() {
+				self.history_redo.push((Modification::Modification, byte_address, Some(previous_value)));
 			}
 		}
 	}
@@ -628,6 +1850,30 @@ impl App {
 
 
 		match modification {
+			Modification::TransactionEnd => {
+				self.history_redo.push((Modification::TransactionEnd, addr, None));
+
+				// keep the cursor (and the selection) where they are
+				let (cursor, offset) = (self.cursor, self.offset);
+
+				// undo every change until we reach the start of the transaction
+				while let Some((modification, start_addr, _)) = self.history.last().copied() {
+					if modification == Modification::TransactionStart {
+						self.history.pop();
+						self.history_redo.push((Modification::TransactionStart, start_addr, None));
+						break;
+					}
+
+					match modification {
+						Modification::Modification => self.restore_overwrites(false),
+						_ => self.undo()
+					}
+				}
+
+				self.offset = offset;
+				self.cursor_jump_to(cursor);
+			},
+			Modification::TransactionStart => {}, // transactions are closed with TransactionEnd
 			Modification::Modification => {
 				
 				// Add the current value to self.history_redo
@@ -637,13 +1883,9 @@ impl App {
 				// For modification, we should always have a value, so we can unwrap()
 				let previous_value = previous_value.unwrap();
 
-				// if the `char` restored is the second `char` of the byte, set the cursor to the
-				// second `char` else set the cursor to the first char
-				if current_val & 0b11110000 == previous_value & 0b11110000 {
-					self.cursor_jump_to(addr * 2 + 1);
-				} else {
-					self.cursor_jump_to(addr * 2);
-				}
+				// set the cursor on the first `char` of the byte that is restored
+				let position = self.view.first_different_digit(current_val, previous_value);
+				self.cursor_jump_to(addr * self.digits() + position);
 
 				// restore the previous value
 				self.write_byte(addr, previous_value, Mode::Overwrite)
@@ -669,7 +1911,7 @@ impl App {
 							)});
 				
 				// move our cursor to the changed location
-				self.jump_to(addr);
+				self.cursor_jump_to(addr * self.digits());
 			},
 			Modification::Insertion => {
 				// Add the current value to self.history_redo
@@ -680,7 +1922,25 @@ impl App {
 				self.remove_byte(addr);
 
 				// Move our cursor at the address of the delete byte
-				self.jump_to(addr);
+				self.cursor_jump_to(addr * self.digits());
+			},
+			Modification::DeletedBlock => {
+				let block = self.history_blocks.pop().unwrap_or_default();
+
+				self.insert_range(addr, &block);
+				self.history_redo.push((Modification::InsertedBlock, addr, None));
+				self.history_redo_blocks.push(block);
+
+				self.cursor_jump_to(addr * self.digits());
+			},
+			Modification::InsertedBlock => {
+				let block = self.history_blocks.pop().unwrap_or_default();
+
+				self.remove_range(addr, block.len() as u64);
+				self.history_redo.push((Modification::DeletedBlock, addr, None));
+				self.history_redo_blocks.push(block);
+
+				self.cursor_jump_to(addr * self.digits());
 			},
 		}
 	}
@@ -698,22 +1958,43 @@ impl App {
 
 
 		match modification {
-			Modification::Modification => {
-				
-				// Add the current value to self.history
-				let current_val = self.read_byte_addr(addr).unwrap();
-				self.history.push((Modification::Modification, addr, Some(current_val)));
+			Modification::TransactionStart => {
+				self.history.push((Modification::TransactionStart, addr, None));
+
+				// keep the cursor (and the selection) where they are
+				let (cursor, offset) = (self.cursor, self.offset);
+
+				// redo every change until we reach the end of the transaction
+				while let Some((modification, _, _)) = self.history_redo.last().copied() {
+					if modification == Modification::TransactionEnd {
+						self.history_redo.pop();
+						self.history.push((Modification::TransactionEnd, addr, None));
+						break;
+					}
+
+					match modification {
+						Modification::Modification => self.restore_overwrites(true),
+						_ => self.redo()
+					}
+				}
+
+				self.offset = offset;
+				self.cursor_jump_to(cursor);
+				return;
+			},
+			Modification::TransactionEnd => {}, // transactions are opened with TransactionStart
+			Modification::Modification => {
+				
+				// Add the current value to self.history
+				let current_val = self.read_byte_addr(addr).unwrap();
+				self.history.push((Modification::Modification, addr, Some(current_val)));
 
 				// For modification, we should always have a value, so we can unwrap()
 				let previous_value = previous_value.unwrap();
 
-				// if the `char` restored is the second `char` of the byte, set the cursor to the
-				// second `char` else set the cursor to the first char
-				if current_val & 0b11110000 == previous_value & 0b11110000 {
-					self.cursor_jump_to(addr * 2 + 1);
-				} else {
-					self.cursor_jump_to(addr * 2);
-				}
+				// set the cursor on the first `char` of the byte that is restored
+				let position = self.view.first_different_digit(current_val, previous_value);
+				self.cursor_jump_to(addr * self.digits() + position);
 
 				// restore the previous value
 				self.write_byte(addr, previous_value, Mode::Overwrite)
@@ -739,7 +2020,7 @@ impl App {
 							)});
 				
 				// move our cursor to the changed location
-				self.jump_to(addr);
+				self.cursor_jump_to(addr * self.digits());
 			},
 			Modification::Insertion => {
 				// Add the current value to self.history
@@ -750,7 +2031,25 @@ impl App {
 				self.remove_byte(addr);
 
 				// Move our cursor at the address of the delete byte
-				self.jump_to(addr);
+				self.cursor_jump_to(addr * self.digits());
+			},
+			Modification::DeletedBlock => {
+				let block = self.history_redo_blocks.pop().unwrap_or_default();
+
+				self.insert_range(addr, &block);
+				self.history.push((Modification::InsertedBlock, addr, None));
+				self.history_blocks.push(block);
+
+				self.cursor_jump_to(addr * self.digits());
+			},
+			Modification::InsertedBlock => {
+				let block = self.history_redo_blocks.pop().unwrap_or_default();
+
+				self.remove_range(addr, block.len() as u64);
+				self.history.push((Modification::DeletedBlock, addr, None));
+				self.history_blocks.push(block);
+
+				self.cursor_jump_to(addr * self.digits());
 			},
 		}
 
@@ -878,27 +2177,25 @@ impl App {
 
 		// If there are only modification (no insertion / deletion)
 		// we can replace the bytes directly in the file
-		match self.no_insertion_or_deletion() {
+		let saved = match self.no_insertion_or_deletion() {
 			true  => self.save_by_overwritting(), 
 			false => self.save_with_temporary_file(),
-		}
-	}
+		};
 
-	/// Read one byte
-	pub fn read_byte(&mut self) -> Option<u8> {
-		let addr = self.last_address_read;
-		self.last_address_read += 1;
+		// the marks are saved with the hash of the file, which has changed
+		if saved.is_ok() {
+			let previous_hash = self.marks_content_hash.take();
 
-		match self.read_byte_addr(addr) {
-			Ok(val) => {
-				Some(val)
-			},
-			Err(_e) => None
+			if !self.marks.is_empty() {
+				self.save_marks(previous_hash.as_deref());
+			}
 		}
+
+		saved
 	}
 
-	// read 16 bytes, and return the length
-	pub fn read_16_length(&mut self) -> (Vec<u8>, usize) {
+	// read a line of `self.bytes_per_line` bytes, and return the length
+	pub fn read_line_length(&mut self) -> (Vec<u8>, usize) {
 		let mut bytes: Vec<u8> = vec![];
 
 		let mut current_address = self.last_address_read;
@@ -908,7 +2205,7 @@ impl App {
 			return (vec![], 0);
 		}
 		
-		for _ in 0..16 {
+		for _ in 0..self.bytes_per_line {
 			// return byte from the file, or modified byte from `self.modified_bytes`
 			match self.read_byte_addr(current_address) {
 				Ok(val) => bytes.push(val),
@@ -940,19 +2237,21 @@ impl App {
 
 		self.offset = self.offset.wrapping_add_signed(direction.into());
 
+		let line = self.bytes_per_line;
+
 		// if offset is beyond the end of file, fix it
-		if self.offset > self.file_size.saturating_sub(0x10) {
+		if self.offset > self.file_size.saturating_sub(line) {
 
 			// handle the last line proprely
-			if self.file_size % 0x10 == 0 { 
-				self.offset = self.file_size - 0x10;
+			if self.file_size.is_multiple_of(line) {
+				self.offset = self.file_size - line;
 			} else {
-				self.offset = self.file_size - (self.file_size % 0x10);
+				self.offset = self.file_size - (self.file_size % line);
 
 				// handle the case where the cursor is just before the last line,
 				// but can't go down without exceeding file size.
-				if self.offset * 2 > self.cursor {
-					self.offset = self.offset - 0x10;
+				if self.offset * self.digits() > self.cursor {
+					self.offset -= line;
 				}
 			}
 		}
@@ -961,9 +2260,11 @@ impl App {
 	// self.cursor = self.cursor + direction
 	// but we check if the address is bellow 0 or lager than the file
 	pub fn change_cursor(&mut self, direction:i64){
+		let line = self.line_digits();
+
 		// check the address is bellow 0
 		if direction.wrapping_add_unsigned(self.cursor.into()) < 0 {
-			self.cursor = 0 + (self.cursor % 0x20);
+			self.cursor %= line; // stay on the same column of the first line
 			return;
 		}
 
@@ -974,41 +2275,41 @@ impl App {
 			Mode::Overwrite => 0
 		};
 
-		let end_of_file = self.file_size * 2 + insertion_mode;
+		let end_of_file = self.file_size * self.digits() + insertion_mode;
 
 		// check if the new cursor address is longer than the file
-		// (file_size * 2) - 1 because we have 2 chars for each hex number.
+		// (file_size * digits) - 1 because we have several chars for each byte.
 		if self.cursor.wrapping_add_signed(direction.into()) > end_of_file.saturating_sub(1) {
 
-			//  + (self.cursor % 0x20) = stay on the same column
+			//  + (self.cursor % line) = stay on the same column
 
 			// case where the last line is an exact fit
-			if end_of_file % 0x20 == 0 {
-				self.cursor = end_of_file.saturating_sub(0x20) + (self.cursor % 0x20); // stay on the same column
+			if end_of_file.is_multiple_of(line) {
+				self.cursor = end_of_file.saturating_sub(line) + (self.cursor % line); // stay on the same column
 			}
 
 			// we have an incomplete last line
 			else {
-				let last_line_length = end_of_file % 0x20;
-				let column_of_cursor = self.cursor % 0x20;
+				let last_line_length = end_of_file % line;
+				let column_of_cursor = self.cursor % line;
 							
-				let start_of_last_line = end_of_file - (end_of_file % 0x20);
+				let start_of_last_line = end_of_file - (end_of_file % line);
 
 				// cursor is on the last line
 				if column_of_cursor < last_line_length {
-					self.cursor = start_of_last_line + (self.cursor % 0x20);
+					self.cursor = start_of_last_line + (self.cursor % line);
 				}
 				
 				// cursor is on the line just before the last, but can't go down
 				// without exceeding file size
 				else {
-					self.cursor = start_of_last_line.saturating_sub(0x20) + (self.cursor % 0x20);
+					self.cursor = start_of_last_line.saturating_sub(line) + (self.cursor % line);
 				}
 
 			}
 
-			if direction == 0x10 { // If we are moving the cursor down
-				self.change_offset(0x10); // move the view one line down
+			if direction == self.bytes_per_line as i64 { // If we are moving the cursor down
+				self.change_offset(self.bytes_per_line as i64); // move the view one line down
 			}
 			return;
 		}
@@ -1016,14 +2317,14 @@ impl App {
 		self.cursor = self.cursor.saturating_add_signed(direction.into());
 
 		// case where the cursor is before what the screen displays
-		if self.cursor / 2 < self.offset {
-			self.offset = (self.cursor / 2) - (self.cursor / 2 % 0x10);
+		if self.cursor / self.digits() < self.offset {
+			self.offset = (self.cursor / self.digits()) - (self.cursor / self.digits() % self.bytes_per_line);
 		}
 
 		// case where the cursor is below what the screen displays
-		if self.cursor / 2 > self.offset + u64::from(self.lines_displayed) * 0x10 {
-			let cursor_line_start = (self.cursor / 2)  - (self.cursor / 2 % 0x10) ;
-			self.offset = cursor_line_start.saturating_sub(u64::from(self.lines_displayed - 1) * 0x10);
+		if self.cursor / self.digits() > self.offset + u64::from(self.lines_displayed) * self.bytes_per_line {
+			let cursor_line_start = (self.cursor / self.digits())  - (self.cursor / self.digits() % self.bytes_per_line) ;
+			self.offset = cursor_line_start.saturating_sub(u64::from(self.lines_displayed - 1) * self.bytes_per_line);
 		}
 	}
 
@@ -1031,7 +2332,7 @@ impl App {
 	pub fn move_selection(&mut self, direction: i64) {
 		// We don't have any selected bytes, just move the cursor
 		if self.selection_start.is_none() {
-			self.change_cursor(direction / 2);
+			self.change_cursor(direction / self.digits() as i64);
 			return;
 		}
 
@@ -1045,7 +2346,7 @@ impl App {
 			return;
 		}
 
-		let end_of_file = self.file_size * 2;
+		let end_of_file = self.file_size * self.digits();
 
 		if end.saturating_add_signed(direction) > end_of_file {
 			return;
@@ -1055,14 +2356,291 @@ impl App {
 		self.selection_start = Some(self.selection_start.unwrap().saturating_add_signed(direction));
 	}
 
-	/// use to jump directly at an address, and move the interface accordingly
+	/// use to jump directly at an address, and move the interface accordingly.
+	/// The current position is added to the jump list
 	pub fn jump_to(&mut self, new_address: u64) {
-		self.cursor_jump_to(new_address * 2);
+		self.record_jump(new_address * self.digits());
+		self.cursor_jump_to(new_address * self.digits());
+	}
+
+	/// Go to the address given by an expression: `:0x1000`, `:$-0x200`, `:'a+4`.
+	/// If `sign` is `+` or `-`, the cursor is moved by this number of bytes
+	pub fn go_to_expression(&mut self, sign: &str, input: &str) {
+		let value = match sign {
+			"" => expression::evaluate_address(input, self),
+			_ => expression::evaluate_signed(input, self)
+		};
+
+		let value = match value {
+			Ok(value) => value,
+			Err(message) => {
+				self.add_error_message(WarningLevel::Warning, message);
+				return;
+			}
+		};
+
+		let address = match sign {
+			"+" => i128::from(self.cursor / self.digits()) + value,
+			"-" => i128::from(self.cursor / self.digits()) - value,
+			_ => value
+		};
+
+		// don't go before the start of the file, or after its end
+		let address = u64::try_from(address.max(0)).unwrap_or(u64::MAX).min(self.file_size);
+
+		// relative moves stay on the same char of the byte
+		let cursor_on_second_char = match sign {
+			"" => 0,
+			_ => self.cursor % self.digits()
+		};
+
+		self.jump_to(address);
+
+		if address < self.file_size {
+			self.cursor += cursor_on_second_char;
+		}
+	}
+
+	/// Read the integer stored at `address`
+	fn read_pointer_at(&mut self, address: u64, format: PointerFormat) -> Result<u64, String> {
+		if address.checked_add(format.size as u64).is_none_or(|end| end > self.file_size) {
+			return Err(format!("Can't read a {} at 0x{:x}, after the end of the file", format, address));
+		}
+
+		let mut bytes = vec![];
+		for i in 0..format.size as u64 {
+			let byte = self.read_byte_addr(address + i)
+				.map_err(|e| format!("Failed to read the {} at 0x{:x}: {}", format, address, e))?;
+			bytes.push(byte);
+		}
+
+		self.reset();
+		Ok(format.decode(&bytes))
+	}
+
+	/// Jump to the address stored at the cursor, plus `self.follow_base` (`p`).
+	/// Go back with follow_back()
+	pub fn follow_pointer(&mut self) {
+		let address = self.cursor / self.digits();
+		let format = self.follow_format;
+
+		let pointer = match self.read_pointer_at(address, format) {
+			Ok(pointer) => pointer,
+			Err(message) => {
+				self.add_error_message(WarningLevel::Warning, message);
+				return;
+			}
+		};
+
+		// pointers in memory dumps point at the displayed addresses
+		let target = match pointer.checked_add(self.follow_base).map(|target| self.file_offset(target)) {
+			Some(Ok(target)) if target < self.file_size => target,
+			Some(Err(message)) => {
+				self.add_error_message(WarningLevel::Warning, message);
+				return;
+			},
+			_ => {
+				self.add_error_message(
+					WarningLevel::Warning,
+					format!("The {} 0x{:x} + 0x{:x} points after the end of the file", format, pointer, self.follow_base)
+				);
+				return;
+			}
+		};
+
+		self.follow_stack.push((self.cursor, self.offset));
+		self.jump_to(target);
+
+		self.add_error_message(
+			WarningLevel::Info,
+			format!(
+				"Followed the {} at 0x{:x} to 0x{:x}. P to go back",
+				format, self.display_address(address), self.display_address(target)
+			)
+		);
+	}
+
+	/// Go back to where we followed the last pointer (`P`)
+	pub fn follow_back(&mut self) {
+		match self.follow_stack.pop() {
+			Some(position) => self.restore_position(position),
+			None => self.add_error_message(WarningLevel::Info, "No pointer followed".to_string())
+		}
+	}
+
+	/// `:follow [u16|u32|u64][le|be] [base]`: set how `p` reads the pointers, and follow the one at the cursor
+	pub fn follow_command(&mut self, arguments: &str) {
+		let mut arguments = arguments.trim();
+
+		if let Some((format, base)) = arguments.split_once(char::is_whitespace).or(Some((arguments, ""))) {
+			if let Some(format) = PointerFormat::parse(format) {
+				self.follow_format = format;
+				arguments = base.trim();
+			}
+		}
+
+		// the base is given by an expression, i.e `:follow u32 'a`
+		self.follow_base = match arguments {
+			"" => 0,
+			base => match expression::evaluate(base, self) {
+				Ok(base) => base,
+				Err(message) => {
+					self.add_error_message(
+						WarningLevel::Warning,
+						format!("{}. Usage: :follow [u16|u32|u64][le|be] [base]", message)
+					);
+					return;
+				}
+			}
+		};
+
+		self.follow_pointer();
+	}
+
+	/// Select the field of the inspector `direction` lines under the selected one
+	pub fn select_inspector_field(&mut self, direction: i64) {
+		let last_field = inspector::fields().len() - 1;
+		self.inspector_index = self.inspector_index.saturating_add_signed(direction as isize).min(last_field);
+	}
+
+	/// Open the command bar to edit the field selected in the inspector, with its current value
+	pub fn edit_inspector_field(&mut self) {
+		let field = inspector::fields()[self.inspector_index];
+		let value = field.decode(&self.cursor_bytes(16)).unwrap_or_default();
+
+		let command = format!(":put {} {}", field.name(), value);
+		self.command_bar = Some(CommandBar { _cursor: command.len() as u64, command });
+		self.editor_mode = CurrentEditor::CommandBar;
+	}
+
+	/// Write a value at the cursor, encoded as a field of the inspector (:put f32le 1.5).
+	/// The bytes are always overwritten, as a single step for undo()
+	pub fn put_command(&mut self, arguments: &str) {
+		let usage = "Usage: :put <field> <value>, i.e :put u32le 0x1234 or :put time32le 2024-03-01 12:00:00";
+
+		let (name, value) = arguments.trim().split_once(char::is_whitespace).unwrap_or((arguments.trim(), ""));
+
+		let field = match Field::parse(name) {
+			Some(field) if !value.trim().is_empty() => field,
+			Some(_) => {
+				self.add_error_message(WarningLevel::Warning, usage.to_string());
+				return;
+			},
+			None => {
+				self.add_error_message(WarningLevel::Warning, format!("Unknown field {}. {}", name, usage));
+				return;
+			}
+		};
+
+		let bytes = match field.encode(value) {
+			Ok(bytes) => bytes,
+			Err(message) => {
+				self.add_error_message(WarningLevel::Warning, message);
+				return;
+			}
+		};
+
+		let address = self.cursor / self.digits();
+
+		if address + bytes.len() as u64 > self.file_size {
+			self.add_error_message(
+				WarningLevel::Warning,
+				format!("Not enough bytes at 0x{:x} to write a {}", self.display_address(address), field.name())
+			);
+			return;
+		}
+
+		self.overwrite_bytes(address, &bytes);
+	}
+
+	/// Add the current position to the jump list, before jumping to another line
+	fn record_jump(&mut self, new_cursor_address: u64) {
+		if new_cursor_address / self.line_digits() == self.cursor / self.line_digits() {
+			return;
+		}
+
+		// forget the positions we went back from
+		self.jump_list.truncate(self.jump_index);
+
+		if self.jump_list.last() != Some(&(self.cursor, self.offset)) {
+			self.push_jump();
+		}
+
+		self.jump_index = self.jump_list.len();
+	}
+
+	/// Add the current position at the end of the jump list, and forget the oldest one if it is full
+	fn push_jump(&mut self) {
+		self.jump_list.push((self.cursor, self.offset));
+
+		if self.jump_list.len() > MAX_JUMPS {
+			self.jump_list.remove(0);
+			self.jump_index = self.jump_index.saturating_sub(1);
+		}
+	}
+
+	/// Go back to the cursor and the offset saved in the jump list
+	fn restore_position(&mut self, (cursor, offset): (u64, u64)) {
+		self.cursor_jump_to(cursor);
+
+		// show the same lines than before the jump, if the cursor is in them
+		let address = self.cursor / self.digits();
+		if offset <= address && address < offset + u64::from(self.lines_displayed) * self.bytes_per_line {
+			self.offset = offset;
+		}
+	}
+
+	/// Go back to the position before the last jump (Ctrl + O)
+	pub fn jump_back(&mut self) {
+		if self.jump_index == 0 {
+			self.add_error_message(WarningLevel::Info, "Already at the oldest jump".to_string());
+			return;
+		}
+
+		// save where we are, to come back with jump_forward()
+		if self.jump_index == self.jump_list.len() {
+			self.push_jump();
+		}
+
+		self.jump_index -= 1;
+		self.restore_position(self.jump_list[self.jump_index]);
+	}
+
+	/// Cancel jump_back() (Ctrl + I)
+	pub fn jump_forward(&mut self) {
+		if self.jump_index + 1 >= self.jump_list.len() {
+			self.add_error_message(WarningLevel::Info, "Already at the newest jump".to_string());
+			return;
+		}
+
+		self.jump_index += 1;
+		self.restore_position(self.jump_list[self.jump_index]);
+	}
+
+	/// List the positions of the jump list in a popup (`:jumps`)
+	pub fn show_jumps(&mut self) {
+		if self.jump_list.is_empty() {
+			self.add_error_message(WarningLevel::Info, "No jumps yet".to_string());
+			return;
+		}
+
+		// the newest jumps first, the current one is marked with `>`
+		let lines = self.jump_list.iter()
+			.enumerate()
+			.rev()
+			.map(|(i, (cursor, _))| {
+				let current = if i == self.jump_index { ">" } else { " " };
+				format!("{} {:>3}  0x{:0width$x}", current, i, self.display_address(cursor / self.digits()), width = self.address_width())
+			})
+			.collect();
+
+		self.list_popup = Some(ListPopup { title: " Jumps: Ctrl + O / Ctrl + I ".to_string(), lines });
+		self.editor_mode = CurrentEditor::ListPopup;
 	}
 
 	/// use to jump directly at an address (using a cursor address), and move the interface accordingly
 	pub fn cursor_jump_to(&mut self, new_cursor_address: u64) {
-		let mut new_address = new_cursor_address / 2;
+		let mut new_address = new_cursor_address / self.digits();
 
 		// check that the address is not bellow the file
 		if new_address > self.file_size {
@@ -1071,15 +2649,15 @@ impl App {
 
 		// if address is not on the page currently displayed,
 		// jump on the address and display it in the middle of the page
-		if (new_address < self.offset) || new_address > self.offset + u64::from(self.lines_displayed)*0x10 - 1{
+		if (new_address < self.offset) || new_address > self.offset + u64::from(self.lines_displayed)*self.bytes_per_line - 1{
 			self.cursor = new_cursor_address;
 
 			// cursor should be in the middle of the screen:
 			// self.offset = self.cursor - (half the screen)
-			let lines_before_cursor = (u64::from(self.lines_displayed)/2) * 0x10;
+			let lines_before_cursor = (u64::from(self.lines_displayed)/2) * self.bytes_per_line;
 			self.offset = u64::saturating_sub(new_address, lines_before_cursor);
 
-			self.offset = self.offset - (self.offset %0x10); // align self.offset to 0x10
+			self.offset = self.offset - (self.offset % self.bytes_per_line); // align self.offset to the start of a line
 		
 		// the new address is displayed on the screen, just move the cursor
 		} else {
@@ -1087,7 +2665,7 @@ impl App {
 		}
 
 		// if cursor is after the end of the file. Go to the end
-		let mut file_end = (self.file_size * 2).saturating_sub(1); // size * 2 - 1 but without going under 0
+		let mut file_end = (self.file_size * self.digits()).saturating_sub(1); // size * digits - 1 but without going under 0
 		if self.mode == Mode::Insert {
 			file_end += 1;
 		}
@@ -1104,6 +2682,7 @@ impl App {
 				let search_size = match match_type {
 					MatchType::Hex => search_results.query_length / 2,
 					MatchType::Text => search_results.query_length,
+					MatchType::Pointer(size) => *size,
 				};
 
 				// address is contained in the matched search results
@@ -1124,7 +2703,7 @@ impl App {
 			let start = if selection < cursor { selection} else { cursor };
 			let end = if selection < cursor { cursor} else { selection };
 
-			if start <= address*2 && address*2 <= end {
+			if start <= address * self.digits() && address * self.digits() <= end {
 				return true
 			}
 		}
@@ -1140,9 +2719,10 @@ impl App {
 			}
 		}
 
+		// the other chars of the byte are on the side of the selection
 		let address = match self.selection_start {
-			Some(selection) if (address > selection) && (address % 2 == 0) => address.saturating_add(1),
-			Some(selection) if (address < selection) && (address % 2 == 1) => address.saturating_sub(1),
+			Some(selection) if address > selection => address - (address % self.digits()) + self.digits() - 1,
+			Some(selection) if address < selection => address - (address % self.digits()),
 			_ => address,
 		};
 
@@ -1159,29 +2739,30 @@ impl App {
 		false
 	}
 
-	/// Return the bytes currently selected
-	pub fn get_selected_bytes(&mut self) -> Option<Vec<u8>> {
-		if self.selection_start.is_none() {
-			return None;
-		}
+	/// Return the range of addresses currently selected: (start, end).
+	/// `end` is excluded from the selection.
+	pub fn selection_range(&self) -> Option<(u64, u64)> {
+		let selection = self.selection_start?;
 
-		let (start_cursor, end_cursor) = match self.selection_start.unwrap() < self.cursor {
-			true  => (self.selection_start.unwrap(), self.cursor + 1),
-			false => (self.cursor, self.selection_start.unwrap()),
+		let (start_cursor, end_cursor) = match selection < self.cursor {
+			true  => (selection, self.cursor + 1),
+			false => (self.cursor, selection),
 		};
 
-		let start = start_cursor / 2;
-		let end = end_cursor / 2;
+		let start = start_cursor / self.digits();
+		let end = end_cursor / self.digits();
 
-		let mut selected_bytes: Vec<u8> = vec![];
-		
-		for addr in start..end {
-			if let Ok(byte) = self.read_byte_addr(addr) {
-				selected_bytes.push(byte);
-			} else {
-				self.add_error_message(WarningLevel::Error, format!("Could not read selected byte at address {:x}", addr));
-				break;
-			}
+		Some((start, end))
+	}
+
+	/// Return the bytes currently selected
+	pub fn get_selected_bytes(&mut self) -> Option<Vec<u8>> {
+		let (start, end) = self.selection_range()?;
+
+		let mut selected_bytes: Vec<u8> = Vec::with_capacity((end - start) as usize);
+
+		if let Err(e) = self.read_edited_range(start, end, |chunk| selected_bytes.extend_from_slice(chunk)) {
+			self.add_error_message(WarningLevel::Error, format!("Could not read the selected bytes: {}", e));
 		}
 
 		Some(selected_bytes)
@@ -1200,6 +2781,133 @@ impl App {
 	// 	}
 	// }
 
+	/// Compute the entropy and the classes of bytes of each block of the file.
+	/// Only the blocks changed since the last time are computed again
+	pub fn update_file_map(&mut self) {
+		let block_size = FileMap::block_size(self.file_size);
+		let blocks = self.file_size.div_ceil(block_size);
+
+		// all the blocks are computed again when their size changes
+		let (first_block, end_block) = match &self.file_map {
+			Some(file_map) if file_map.block_size == block_size => match file_map.edited {
+				Some((start, end)) => (start / block_size, min(end.div_ceil(block_size), blocks)),
+				None => return
+			},
+			_ => (0, blocks)
+		};
+
+		let mut file_map = match self.file_map.take() {
+			Some(file_map) if file_map.block_size == block_size => file_map,
+			_ => FileMap { block_size, entropies: vec![], classes: vec![], edited: None }
+		};
+		file_map.entropies.resize(blocks as usize, 0.0);
+		file_map.classes.resize(blocks as usize, ByteClass::Null);
+		file_map.edited = None;
+
+		// Go from a sample to the next one, instead of walking self.modified_bytes from the start for each one
+		let mut address = first_block * block_size;
+		let mut real_address = self.get_real_address(address);
+
+		for block in first_block..end_block {
+			let block_start = block * block_size;
+			let block_end = min(block_start + block_size, self.file_size);
+
+			let mut counts = [0u64; 256];
+			let read = FileMap::sample_ranges(block_start, block_end).into_iter()
+				.try_for_each(|(start, end)| {
+					real_address = self.skip_real_address(real_address, start - address);
+					address = start;
+
+					self.read_real_range(real_address, end - start, |bytes| {
+						for byte in bytes {
+							counts[*byte as usize] += 1;
+						}
+					})
+				});
+
+			if read.is_err() {
+				file_map.entropies.truncate(block as usize);
+				file_map.classes.truncate(block as usize);
+				break;
+			}
+			file_map.entropies[block as usize] = stats::entropy(&counts);
+			file_map.classes[block as usize] = stats::dominant_class(&counts);
+		}
+
+		self.file_map = Some(file_map);
+		self.reset();
+	}
+
+	/// Jump to the start of the next region with a high entropy (compressed or encrypted data),
+	/// or with a low entropy (padding, tables) if `high_entropy` is false
+	pub fn go_to_next_entropy_region(&mut self, high_entropy: bool) {
+		self.update_file_map();
+
+		let Some(file_map) = &self.file_map else {
+			return;
+		};
+
+		let next_region = match high_entropy {
+			true => file_map.next_region(self.cursor / self.digits(), |entropy| entropy >= stats::HIGH_ENTROPY),
+			false => file_map.next_region(self.cursor / self.digits(), |entropy| entropy < stats::LOW_ENTROPY)
+		};
+
+		match next_region {
+			Some(address) => self.jump_to(address),
+			None => {
+				let region = if high_entropy { "high" } else { "low" };
+				self.add_error_message(
+					WarningLevel::Info,
+					format!("No {} entropy region after 0x{:x}", region, self.display_address(self.cursor / self.digits()))
+				);
+			}
+		}
+	}
+
+	/// Ranges of the addresses (end excluded) with inserted or overwritten bytes,
+	/// and addresses where bytes have been deleted
+	pub fn modified_ranges(&self) -> Vec<(u64, u64)> {
+		let mut ranges = vec![];
+		let mut shift: i64 = 0; // difference between the addresses in the file, and in our buffer
+
+		for (file_address, changes) in &self.modified_bytes {
+			let address = (*file_address as i64 + shift) as u64;
+
+			match changes {
+				Changes::Insertion(inserted_bytes) => {
+					ranges.push((address, address + inserted_bytes.len() as u64));
+					shift += inserted_bytes.len() as i64 - 1;
+				},
+				Changes::Deleted => {
+					ranges.push((address, address + 1));
+					shift -= 1;
+				}
+			}
+		}
+
+		ranges
+	}
+
+	/// Jump to the part of the file under a click on the minimap.
+	/// Only the click is added to the jump list, not the positions we drag the mouse on
+	pub fn click_minimap(&mut self, column: u16, row: u16, dragging: bool) {
+		let area = self.minimap_area;
+
+		if !self.show_minimap || self.file_size == 0 || !area.contains((column, row).into()) {
+			return;
+		}
+
+		let line_size = stats::line_size(self.file_size, area.height.into());
+		let address = u64::from(row - area.y) * line_size;
+
+		let new_address = min(address, self.file_size - 1);
+
+		match dragging {
+			true => self.cursor_jump_to(new_address * self.digits()),
+			false => self.jump_to(new_address)
+		}
+	}
+
 	/// jump to the search first result after our cursor
 	pub fn go_to_next_search_result(&mut self) {
 
@@ -1212,7 +2920,7 @@ impl App {
 		// find the first search result with an address
 		// that is after our current cursor
 
-		let current_address = self.cursor / 2;
+		let current_address = self.cursor / self.digits();
 		let mut new_address: Option<u64> = None;
 		
 		for (addr, _) in &search_results.match_addresses {
@@ -1228,31 +2936,636 @@ impl App {
 		}
 	}
 
-	/// jump to the search first result before our cursor
-	pub fn go_to_previous_search_result(&mut self) {
+	/// jump to the search first result before our cursor
+	pub fn go_to_previous_search_result(&mut self) {
+
+		// if we don't have any search results, return
+		let search_results = match &self.search_results {
+			None => {return},
+			Some(search_results) => {search_results}
+		};
+		
+		// find the first search result with an address
+		// that is after our current cursor
+
+		let current_address = self.cursor / self.digits();
+		let mut new_address: Option<u64> = None;
+		
+		for (addr, _) in (&(&search_results).match_addresses).into_iter().rev() {
+			if *addr < current_address {
+				new_address = Some(*addr);
+				break;
+			}
+		}
+
+		// and jump to it. If we found one
+		if let Some(new_addr) = new_address {
+			self.jump_to(new_addr);
+		}
+	}
+
+	/// Search the places that point at the file offset `target`: the 32 and 64 bits encodings,
+	/// little and big endian, of its displayed address plus `base`. The results replace the search results
+	pub fn find_xrefs(&mut self, target: u64, base: u64) {
+		let target = self.display_address(target);
+		let value = match target.checked_add(base) {
+			Some(value) => value,
+			None => {
+				self.add_error_message(WarningLevel::Warning, "The base overflows".to_string());
+				return;
+			}
+		};
+
+		let mut patterns = vec![value.to_le_bytes().to_vec(), value.to_be_bytes().to_vec()];
+		if let Ok(value) = u32::try_from(value) {
+			patterns.push(value.to_le_bytes().to_vec());
+			patterns.push(value.to_be_bytes().to_vec());
+		}
+
+		let description = match base {
+			0 => format!("0x{:x}", target),
+			_ => format!("0x{:x} (0x{:x} with the base)", target, value)
+		};
+
+		self.search_results = None;
+
+		let file_copy = self.file.try_clone().unwrap();
+		match search_patterns(file_copy, &patterns) {
+			Err(_e) => {
+				self.add_error_message(
+					WarningLevel::Error,
+					"Error: cross-references search failed".to_string()
+				);
+			},
+			Ok(Some(search_results)) => {
+				let count = search_results.match_addresses.len();
+				self.search_results = Some(search_results);
+				self.go_to_next_search_result();
+
+				self.add_error_message(
+					WarningLevel::Info,
+					format!("{} cross-references to {}. n / N to go through them", count, description)
+				);
+			},
+			Ok(None) => {
+				self.add_error_message(
+					WarningLevel::Info,
+					format!("No cross-references to {}", description)
+				);
+			}
+		};
+	}
+
+	/// `:xref [value] [base B]`: find the cross-references to the cursor, or to `value`
+	pub fn xref_command(&mut self, arguments: &str) {
+		let arguments = arguments.trim();
+
+		let (target, base) = match arguments.split_once("base") {
+			Some((target, base)) => (target.trim(), base.trim()),
+			None => (arguments, "")
+		};
+
+		let target = match target {
+			"" => Ok(self.cursor / self.digits()),
+			target => expression::evaluate_offset(target, self)
+		};
+
+		let base = match base {
+			"" => Ok(0),
+			base => expression::evaluate(base, self)
+		};
+
+		match (target, base) {
+			(Ok(target), Ok(base)) => self.find_xrefs(target, base),
+			(Err(message), _) | (_, Err(message)) => self.add_error_message(
+				WarningLevel::Warning,
+				format!("{}. Usage: :xref [value] [base B]", message)
+			)
+		}
+	}
+
+	/// Change a setting of the interface with `:set <option> <value>`. Without a value, show the current one
+	pub fn set_option(&mut self, option: &str, value: &str) {
+		let value = value.trim();
+
+		match (option, value) {
+			("base", "") => self.add_error_message(
+				WarningLevel::Info,
+				format!("base = 0x{:x}", self.base_address)
+			),
+			("base", value) => match parse_number(value) {
+				Some(base_address) => self.base_address = base_address,
+				None => self.add_error_message(WarningLevel::Warning, format!("Invalid base address {}", value))
+			},
+			("group", "") => {
+				let endianness = if self.group_little_endian { " le" } else { "" };
+				self.add_error_message(WarningLevel::Info, format!("group = {}{}", self.group_size, endianness));
+			},
+			("group", value) => {
+				let (size, endianness) = value.split_once(char::is_whitespace).unwrap_or((value, "be"));
+
+				match (size, endianness.trim()) {
+					("1" | "2" | "4" | "8", endianness @ ("le" | "be")) => {
+						self.group_size = size.parse().unwrap();
+						self.group_little_endian = endianness == "le";
+					},
+					_ => self.add_error_message(
+						WarningLevel::Warning,
+						"Usage: :set group 1|2|4|8 [le|be]".to_string()
+					)
+				}
+			},
+			("columns", "") => {
+				let columns = match self.columns {
+					Columns::Fixed(_) => format!("{}", self.bytes_per_line),
+					Columns::Auto => format!("auto ({})", self.bytes_per_line)
+				};
+				self.add_error_message(WarningLevel::Info, format!("columns = {}", columns));
+			},
+			("columns", value) => match Columns::parse(value) {
+				Some(columns) => {
+					self.columns = columns;
+					self.auto_columns_layout = None;
+
+					// the ui computes the number of columns in auto mode
+					if let Columns::Fixed(bytes_per_line) = columns {
+						self.set_bytes_per_line(bytes_per_line);
+					}
+				},
+				None => self.add_error_message(
+					WarningLevel::Warning,
+					format!("Invalid number of columns {}, use 1 to {} or auto", value, Columns::MAX)
+				)
+			},
+			("view", "") => self.add_error_message(WarningLevel::Info, format!("view = {}", self.view.name())),
+			("view", value) => match View::parse(value) {
+				Some(view) => self.set_view(view),
+				None => self.add_error_message(
+					WarningLevel::Warning,
+					"Usage: :set view bin|oct|dec|hex".to_string()
+				)
+			},
+			("encoding", "") => self.add_error_message(WarningLevel::Info, format!("encoding = {}", self.encoding.name())),
+			("encoding", value) => match Encoding::parse(value) {
+				Some(encoding) => self.encoding = encoding,
+				None => self.add_error_message(
+					WarningLevel::Warning,
+					"Usage: :set encoding ascii|latin1|cp437|cp037|utf8|utf16le|utf16be".to_string()
+				)
+			},
+			_ => self.add_error_message(WarningLevel::Warning, format!("Unknown option {}", option))
+		}
+	}
+
+	/// Fill the bytes from `start` to `end` (excluded), using the arguments of the `:fill` command:
+	/// an hex pattern (`9090`), incrementing bytes (`inc 00`) or random bytes (`random [seed]`)
+	pub fn fill(&mut self, start: u64, end: u64, arguments: &str) {
+		if start >= end || end > self.file_size {
+			self.add_error_message(
+				WarningLevel::Warning,
+				format!("The range 0x{:x}..0x{:x} is outside of the file", self.display_address(start), self.display_address(end))
+			);
+			return;
+		}
+
+		let len = (end - start) as usize;
+		let mut arguments = arguments.split_whitespace();
+
+		let (bytes, description) = match (arguments.next(), arguments.next(), arguments.next()) {
+			// incrementing bytes: `:fill inc 00`
+			(Some("inc"), first_byte, None) => {
+				match parse_hex_bytes(first_byte.unwrap_or("00")).as_deref() {
+					Some([first_byte]) => (fill_incrementing(*first_byte, len), String::from("incrementing bytes")),
+					_ => {
+						self.add_error_message(WarningLevel::Warning, "Usage: :fill inc <first byte>, i.e `:fill inc 00`".to_string());
+						return;
+					}
+				}
+			},
+
+			// random bytes: `:fill random 1234`, the seed is optionnal
+			(Some("random"), seed, None) => {
+				let seed = match seed {
+					Some(seed) => parse_number(seed),
+					None => Some(SystemTime::now()
+						.duration_since(UNIX_EPOCH)
+						.map(|time| time.as_nanos() as u64)
+						.unwrap_or_default())
+				};
+
+				match seed {
+					Some(seed) => (fill_random(seed, len), format!("random bytes (seed: {})", seed)),
+					None => {
+						self.add_error_message(WarningLevel::Warning, "Failed to parse the seed".to_string());
+						return;
+					}
+				}
+			},
+
+			// hex pattern repeated over the range: `:fill 9090`
+			(Some(pattern), None, None) => {
+				match parse_hex_bytes(pattern) {
+					Some(bytes) => (fill_pattern(&bytes, len), format!("the pattern {}", pattern)),
+					None => {
+						self.add_error_message(WarningLevel::Warning, format!("Invalid hex pattern: {}", pattern));
+						return;
+					}
+				}
+			},
+
+			_ => {
+				self.add_error_message(WarningLevel::Warning, "Usage: :fill [range] <hex pattern | inc 00 | random [seed]>".to_string());
+				return;
+			}
+		};
+
+		self.overwrite_bytes(start, &bytes);
+
+		self.add_error_message(
+			WarningLevel::Info,
+			format!("Filled 0x{:x} bytes at 0x{:x} with {}", len, self.display_address(start), description)
+		);
+	}
+
+	/// Compute the hash of the selection displayed in the preview pane,
+	/// if the selection or the bytes changed since the last time
+	pub fn update_preview_digest(&mut self) {
+		let (Some(algorithm), Some((start, end))) = (self.preview_hash, self.selection_range()) else {
+			self.preview_digest = None;
+			return;
+		};
+
+		if self.preview_digest.as_ref().is_some_and(|cache| cache.algorithm == algorithm
+			&& cache.start == start && cache.end == end && cache.changes_count == self.changes_count) {
+			return;
+		}
+
+		let mut hasher = algorithm.hasher();
+
+		self.preview_digest = match self.read_edited_range(start, end, |bytes| hasher.update(bytes)) {
+			Ok(()) => Some(SelectionDigest { algorithm, start, end, changes_count: self.changes_count, digest: hasher.finalize() }),
+			Err(_) => None
+		};
+		self.reset();
+	}
+
+	/// Compute a checksum or a hash of the selection, or of the whole file if nothing is selected.
+	/// The unsaved changes are included. Copy the result to the clipboard if `copy` is true
+	pub fn hash(&mut self, algorithm: Algorithm, copy: bool) {
+		let (start, end, description) = match self.selection_range() {
+			Some((start, end)) => (start, end, "the selection"),
+			None => (0, self.file_size, "the file")
+		};
+
+		let mut hasher = algorithm.hasher();
+
+		if let Err(e) = self.read_edited_range(start, end, |bytes| hasher.update(bytes)) {
+			self.add_error_message(WarningLevel::Error, format!("Failed to read the bytes: {}", e));
+			return;
+		}
+
+		let digest: String = hasher.finalize()
+			.iter()
+			.map(|byte| format!("{:02x}", byte))
+			.collect();
+
+		// display the hash in the preview pane while the bytes are selected
+		self.preview_hash = Some(algorithm);
+
+		if copy {
+			self.copy_to_system_clipboard(&digest);
+		}
+
+		self.add_error_message(
+			WarningLevel::Info,
+			format!("{} of {} (0x{:x} bytes){}: {}", algorithm.name(), description, end - start,
+				if copy {", copied"} else {""}, digest)
+		);
+	}
+
+	/// Hash of the file saved on the disk (without the unsaved changes), and its size.
+	/// The whole file is read: it is computed when the marks are loaded, and after each save
+	fn content_hash(&mut self) -> Result<String, std::io::Error> {
+		const CHUNK_SIZE: usize = 0x10000;
+
+		let mut hasher = Algorithm::Crc32.hasher();
+		let mut size = 0;
+
+		loop {
+			let bytes = self.read_file_bytes(size, CHUNK_SIZE)?;
+			if bytes.is_empty() {
+				break;
+			}
+
+			hasher.update(&bytes);
+			size += bytes.len() as u64;
+		}
+
+		let crc: String = hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect();
+		self.reset();
+
+		Ok(format!("{}-{:x}", crc, size))
+	}
+
+	/// Load the marks saved for this file. They are only used if the file has not changed since
+	fn load_marks(&mut self) {
+		let entries = state::load_entries("marks", &self.file_path);
+
+		// don't read the whole file if there is no mark
+		if entries.is_empty() {
+			return;
+		}
+
+		let Ok(content_hash) = self.content_hash() else {
+			return;
+		};
+
+		self.marks = entries.iter()
+			.filter_map(|entry| marks::parse_entry(entry))
+			.filter(|(hash, _, _)| *hash == content_hash)
+			.map(|(_, name, mark)| (name, mark))
+			.collect();
+
+		self.marks_content_hash = Some(content_hash);
+	}
+
+	/// Save the marks of this file. The marks saved for other versions of the file are kept,
+	/// except the ones of `replaced_hash`: the version we just saved over.
+	/// The hash of the file is computed only the first time, then it is kept in `self.marks_content_hash`
+	fn save_marks(&mut self, replaced_hash: Option<&str>) {
+		let content_hash = match self.marks_content_hash.clone() {
+			Some(content_hash) => content_hash,
+			None => match self.content_hash() {
+				Ok(content_hash) => content_hash,
+				Err(e) => {
+					self.add_error_message(WarningLevel::Warning, format!("Failed to save the marks: {}", e));
+					return;
+				}
+			}
+		};
+
+		let mut entries: Vec<String> = state::load_entries("marks", &self.file_path)
+			.into_iter()
+			.filter(|entry| marks::parse_entry(entry)
+				.is_some_and(|(hash, _, _)| hash != content_hash && Some(hash) != replaced_hash))
+			.collect();
+
+		entries.extend(self.marks.iter().map(|(name, mark)| marks::to_entry(&content_hash, *name, mark)));
+
+		if let Err(e) = state::save_entries("marks", &self.file_path, &entries) {
+			self.add_error_message(WarningLevel::Warning, format!("Failed to save the marks: {}", e));
+		}
+
+		self.marks_content_hash = Some(content_hash);
+	}
+
+	/// Set the mark `name` at the cursor (`m<a-z>` or `:mark <a-z> [label]`)
+	pub fn set_mark(&mut self, name: char, label: &str) {
+		if !marks::is_mark_name(name) {
+			self.add_error_message(WarningLevel::Warning, format!("Invalid mark '{}', use a letter from a to z", name));
+			return;
+		}
+
+		// the label is saved on a line of the state file, separated by tabs
+		if label.chars().any(|c| c.is_control()) {
+			self.add_error_message(WarningLevel::Warning, "The label of a mark can't contain tabs or newlines".to_string());
+			return;
+		}
+
+		let address = self.cursor / self.digits();
+		self.marks.insert(name, Mark { address, label: label.to_string() });
+		self.save_marks(None);
+
+		if self.error_msg.is_none() {
+			self.add_error_message(WarningLevel::Info, format!("Mark '{}' set at 0x{:x}", name, self.display_address(address)));
+		}
+	}
+
+	/// Jump to the mark `name` (`'<a-z>`)
+	pub fn jump_to_mark(&mut self, name: char) {
+		match self.marks.get(&name) {
+			Some(mark) if self.file_size > 0 => self.jump_to(min(mark.address, self.file_size - 1)),
+			_ => self.add_error_message(WarningLevel::Info, format!("Mark '{}' is not set", name))
+		}
+	}
+
+	/// Remove the mark `name` (`:delmark <a-z>`)
+	pub fn delete_mark(&mut self, name: char) {
+		match self.marks.remove(&name) {
+			Some(_) => {
+				self.save_marks(None);
+				self.add_error_message(WarningLevel::Info, format!("Mark '{}' removed", name));
+			},
+			None => self.add_error_message(WarningLevel::Info, format!("Mark '{}' is not set", name))
+		}
+	}
+
+	/// List the marks in a popup (`:marks`)
+	pub fn show_marks(&mut self) {
+		if self.marks.is_empty() {
+			self.add_error_message(WarningLevel::Info, "No marks. Use m<a-z> to set one".to_string());
+			return;
+		}
+
+		let lines = self.marks.iter()
+			.map(|(name, mark)| {
+				format!("{}  0x{:0width$x}  {}", name, self.display_address(mark.address), mark.label, width = self.address_width())
+			})
+			.collect();
+
+		self.list_popup = Some(ListPopup { title: " Marks: '<a-z> to jump ".to_string(), lines });
+		self.editor_mode = CurrentEditor::ListPopup;
+	}
+
+	/// Count the bytes of the selection, or of the whole file, and display the statistics in a popup
+	pub fn show_stats(&mut self) {
+		let (start, end, description) = match self.selection_range() {
+			Some((start, end)) => (start, end, "the selection"),
+			None => (0, self.file_size, "the file")
+		};
+
+		let mut counts = [0u64; 256];
+
+		if let Err(e) = self.read_edited_range(start, end, |bytes| {
+			for byte in bytes {
+				counts[*byte as usize] += 1;
+			}
+		}) {
+			self.add_error_message(WarningLevel::Error, format!("Failed to read the bytes: {}", e));
+			return;
+		}
+
+		self.byte_stats = Some(ByteStats { start, end, description, counts });
+		self.editor_mode = CurrentEditor::StatsPopup;
+		self.reset();
+	}
+
+	/// Compute the checksum described by `preset`, and write it in the file.
+	/// Return the checksum, or a message if it can't be written
+	fn write_checksum(&mut self, preset: &ChecksumPreset) -> Result<Vec<u8>, String> {
+		if preset.start >= preset.end || preset.end > self.file_size {
+			return Err(format!(
+				"The range 0x{:x}..0x{:x} is outside of the file", self.display_address(preset.start), self.display_address(preset.end)
+			));
+		}
+
+		let mut hasher = preset.algorithm.hasher();
+		self.read_edited_range(preset.start, preset.end, |bytes| hasher.update(bytes))
+			.map_err(|e| format!("Failed to read the bytes: {}", e))?;
+		let digest = hasher.finalize();
+
+		let checksum_end = preset.destination + digest.len() as u64;
+
+		if checksum_end > self.file_size {
+			return Err(format!("The checksum doesn't fit at 0x{:x}", self.display_address(preset.destination)));
+		}
+		if preset.destination < preset.end && preset.start < checksum_end {
+			return Err(format!(
+				"The checksum at 0x{:x} can't be inside the range it is computed on", self.display_address(preset.destination)
+			));
+		}
+
+		self.overwrite_bytes(preset.destination, &preset.encode(&digest));
+
+		Ok(digest)
+	}
+
+	/// `preset` with display addresses, to show it to the user
+	fn display_preset(&self, preset: &ChecksumPreset) -> ChecksumPreset {
+		ChecksumPreset {
+			start: self.display_address(preset.start),
+			end: self.display_address(preset.end),
+			destination: self.display_address(preset.destination),
+			..preset.clone()
+		}
+	}
+
+	/// `:fixcrc <algorithm> <range> <destination> [le|be]` writes the checksum of a range at `destination`,
+	/// and remembers it for this file. `:fixcrc` alone writes again all the checksums remembered
+	pub fn fix_checksums(&mut self, arguments: &str) {
+		const PRESETS_FILE: &str = "checksums";
+
+		let mut presets: Vec<ChecksumPreset> = state::load_entries(PRESETS_FILE, &self.file_path)
+			.iter()
+			.filter_map(|entry| ChecksumPreset::parse(entry, &mut NoContext).ok())
+			.collect();
+
+		match arguments.trim() {
+			"" if presets.is_empty() => {
+				self.add_error_message(
+					WarningLevel::Info,
+					"No checksum saved for this file. Usage: :fixcrc crc32 0x10..0x1000 0x8 [le|be]".to_string()
+				);
+			},
+
+			// update all the checksums saved for this file, in a single step for undo()
+			"" => {
+				self.start_transaction(self.cursor / self.digits());
+
+				let mut updated: Vec<String> = vec![];
+				for preset in &presets {
+					match self.write_checksum(preset) {
+						Ok(_) => updated.push(format!("{} at 0x{:x}", preset.algorithm.name(), self.display_address(preset.destination))),
+						Err(message) => updated.push(format!("{} ({})", self.display_preset(preset), message))
+					}
+				}
+
+				self.end_transaction(self.cursor / self.digits());
+				self.add_error_message(WarningLevel::Info, format!("Checksums updated: {}", updated.join(", ")));
+			},
+
+			"list" => {
+				let list: Vec<String> = presets.iter().map(|preset| self.display_preset(preset).to_string()).collect();
+				self.add_error_message(WarningLevel::Info, format!("Checksums saved for this file: {}", list.join(", ")));
+			},
+
+			"clear" => {
+				match state::save_entries(PRESETS_FILE, &self.file_path, &[]) {
+					Ok(()) => self.add_error_message(WarningLevel::Info, "Checksums removed for this file".to_string()),
+					Err(e) => self.add_error_message(WarningLevel::Error, format!("Failed to remove the checksums: {}", e))
+				}
+			},
+
+			arguments => {
+				// the addresses typed can be display addresses, the presets are saved with file offsets
+				let preset = match ChecksumPreset::parse(arguments, self) {
+					Ok(preset) => preset,
+					Err(message) => {
+						self.add_error_message(WarningLevel::Warning, message);
+						return;
+					}
+				};
+
+				let digest = match self.write_checksum(&preset) {
+					Ok(digest) => digest,
+					Err(message) => {
+						self.add_error_message(WarningLevel::Warning, message);
+						return;
+					}
+				};
+
+				// remember the checksum, it replaces the one written at the same address
+				presets.retain(|saved_preset| saved_preset.destination != preset.destination);
+				presets.push(preset.clone());
+
+				let entries: Vec<String> = presets.iter().map(|preset| preset.to_string()).collect();
+				let saved = match state::save_entries(PRESETS_FILE, &self.file_path, &entries) {
+					Ok(()) => "run `:fixcrc` to update it",
+					Err(_) => "failed to save it for the next time"
+				};
+
+				let digest: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+				self.add_error_message(
+					WarningLevel::Info,
+					format!("{} 0x{} written at 0x{:x}, {}", preset.algorithm.name(), digest, self.display_address(preset.destination), saved)
+				);
+			}
+		}
+	}
+
+	/// Copy `text` to the clipboard of the terminal, with an OSC 52 escape sequence
+	pub fn copy_to_system_clipboard(&mut self, text: &str) {
+		if execute!(std::io::stdout(), CopyToClipboard::to_clipboard_from(text)).is_err() {
+			self.add_error_message(WarningLevel::Warning, "Failed to copy to the clipboard".to_string());
+		}
+	}
 
-		// if we don't have any search results, return
-		let search_results = match &self.search_results {
-			None => {return},
-			Some(search_results) => {search_results}
+	/// Apply a transformation (xor, rol, ...) to the selected bytes, as a single step for undo()
+	pub fn apply_transform(&mut self, transform: &Transform) {
+		let (start, _) = match self.selection_range() {
+			Some(range) => range,
+			None => {
+				self.add_error_message(WarningLevel::Info, "Select the bytes to transform first".to_string());
+				return;
+			}
 		};
-		
-		// find the first search result with an address
-		// that is after our current cursor
 
-		let current_address = self.cursor / 2;
-		let mut new_address: Option<u64> = None;
-		
-		for (addr, _) in (&(&search_results).match_addresses).into_iter().rev() {
-			if *addr < current_address {
-				new_address = Some(*addr);
-				break;
-			}
-		}
+		let selected_bytes = self.get_selected_bytes().unwrap_or_default();
+		let transformed_bytes = transform.apply(&selected_bytes);
 
-		// and jump to it. If we found one
-		if let Some(new_addr) = new_address {
-			self.jump_to(new_addr);
+		self.overwrite_bytes(start, &transformed_bytes);
+
+		// Count the words modified (bytes for bitwise operations)
+		let word_size = transform.word_size();
+		let changed_words = selected_bytes.chunks(word_size)
+			.zip(transformed_bytes.chunks(word_size))
+			.filter(|(before, after)| before != after)
+			.count();
+
+		let unit = match word_size {
+			1 => "bytes",
+			_ => "words"
+		};
+
+		match selected_bytes.len() % word_size {
+			0 => self.add_error_message(
+				WarningLevel::Info,
+				format!("{} {} changed", changed_words, unit)
+			),
+			remaining => self.add_error_message(
+				WarningLevel::Warning,
+				format!("{} {} changed. The last {} bytes are not a whole word and were not changed", changed_words, unit, remaining)
+			)
 		}
 	}
 
@@ -1279,29 +3592,196 @@ impl App {
 			exit(0);
 		}
 
-		// command is hex address (:0x...), we jump to this address
-		let hexnum_regex = Regex::new(r"^:\s?+0[xX][0-9a-fA-F]+$").unwrap();
-		if hexnum_regex.is_match(command) {
-
-			// strip spaces and the 0x at the start
-			command.remove(0); // remove ':' at the start
+		// go to an address, given by an expression (:0x1000, :$-0x200, :.+4*0x20, :'a, :50%, :[0x3c]).
+		// File offsets start with @ (:@0x50). Relative moves start with + or - (:+0x10, :-10)
+		let goto_regex = Regex::new(r"^:\s?+([-+]?)\s?+([0-9$.('\[@].*)$").unwrap();
+		if let Some(capture) = goto_regex.captures(command) {
+			self.go_to_expression(&capture[1], &capture[2]);
+			return;
+		}
 
-			// remove the 0x or 0X at the start
-			let command =
-			if let Some(command) = command.trim().strip_prefix("0x") {
-				command
-			} else {
-				command.trim().strip_prefix("0X").unwrap()
+		// fill the selection or a range with a pattern (:fill 90, :fill 0x100..0x200 90, :fill inc 00, :fill random)
+		let fill_regex = Regex::new(r"^:\s?+fill(\s+\S+\.\.\S+)?\s+(.+)$").unwrap();
+		if let Some(capture) = fill_regex.captures(command) {
+			let range = match capture.get(1) {
+				Some(range) => match expression::evaluate_range(range.as_str().trim(), self) {
+					Ok(range) => range,
+					Err(message) => {
+						self.add_error_message(WarningLevel::Warning, format!("Failed to parse the range: {}", message));
+						return;
+					}
+				},
+				None => match self.selection_range() {
+					Some(range) => range,
+					None => {
+						self.add_error_message(
+							WarningLevel::Info,
+							"Select some bytes, or give a range: `:fill 0x100..0x200 90`".to_string()
+						);
+						return;
+					}
+				}
 			};
 
-			// convert hex string to u64
-			let parse_address = u64::from_str_radix(command, 16);
+			self.fill(range.0, range.1, &capture[2]);
+			return;
+		}
+
+		// transformations of the selection (:xor 4142, :not, :rol 3, :reverse, :add u32le 0x1000, ...)
+		let transform_regex = Regex::new(r"^:\s?+(xor|and|or|not|shl|shr|rol|ror|reverse|swapnibbles|bswap|add|sub|mul)(\s|$)").unwrap();
+		if transform_regex.is_match(command) {
+			match Transform::parse(command) {
+				Ok(transform) => self.apply_transform(&transform),
+				Err(message) => self.add_error_message(WarningLevel::Warning, message)
+			}
+			return;
+		}
+
+		// move the selected bytes to another address (:move 0x100)
+		let move_regex = Regex::new(r"^:\s?+move\s+(\S+)\s?+$").unwrap();
+		if let Some(capture) = move_regex.captures(command) {
+			match expression::evaluate_offset(&capture[1], self) {
+				Ok(destination) => self.move_block(destination),
+				Err(message) => self.add_error_message(WarningLevel::Warning, format!("Failed to parse the address: {}", message))
+			}
+			return;
+		}
+
+		// marks: list them, set one with a label (:mark a header), or remove one
+		if command == ":marks" {
+			self.show_marks();
+			return;
+		}
+
+		// follow the pointer at the cursor (:follow u32le 0x400)
+		let follow_regex = Regex::new(r"^:\s?+follow(\s.*)?$").unwrap();
+		if let Some(capture) = follow_regex.captures(command) {
+			let arguments = capture.get(1).map_or("", |arguments| arguments.as_str());
+			self.follow_command(arguments);
+			return;
+		}
+
+		// write a value decoded by the inspector (:put f32le 1.5)
+		let put_regex = Regex::new(r"^:\s?+put(\s.*)?$").unwrap();
+		if let Some(capture) = put_regex.captures(command) {
+			let arguments = capture.get(1).map_or("", |arguments| arguments.as_str());
+			self.put_command(arguments);
+			return;
+		}
+
+		// change a setting (:set base 0x08000000)
+		let set_regex = Regex::new(r"^:\s?+set\s+(\w+)(\s.*)?$").unwrap();
+		if let Some(capture) = set_regex.captures(command) {
+			let value = capture.get(2).map_or("", |value| value.as_str());
+			self.set_option(&capture[1], value);
+			return;
+		}
+
+		// search the pointers to the cursor (:xref, :xref 0x400 base 0x400000)
+		let xref_regex = Regex::new(r"^:\s?+xref(\s.*)?$").unwrap();
+		if let Some(capture) = xref_regex.captures(command) {
+			let arguments = capture.get(1).map_or("", |arguments| arguments.as_str());
+			self.xref_command(arguments);
+			return;
+		}
+
+		// list the positions we can go back to with Ctrl + O
+		if command == ":jumps" {
+			self.show_jumps();
+			return;
+		}
+
+		let mark_regex = Regex::new(r"^:\s?+(mark|delmark)\s+(\S)(\s+.*)?$").unwrap();
+		if let Some(capture) = mark_regex.captures(command) {
+			let name = capture[2].chars().next().unwrap_or(' ');
+			let label = capture.get(3).map_or("", |label| label.as_str().trim());
+
+			match &capture[1] {
+				"mark" => self.set_mark(name, label),
+				_ => self.delete_mark(name)
+			}
+			return;
+		}
+
+		// statistics on the bytes of the selection or the file
+		if command == ":stats" {
+			self.show_stats();
+			return;
+		}
+
+		// checksum or hash of the selection or of the file (:hash, :hash crc32, :hash md5 copy)
+		if command == ":hash off" {
+			self.preview_hash = None;
+			return;
+		}
+
+		let hash_regex = Regex::new(r"^:\s?+hash(\s+[\w-]+?)??(\s+copy)?\s?+$").unwrap();
+		if let Some(capture) = hash_regex.captures(command) {
+			let algorithm = match capture.get(1) {
+				Some(name) => Algorithm::parse(name.as_str().trim()),
+				None => Some(Algorithm::Sha256)
+			};
 
-			match parse_address {
-				Ok(address) => {self.jump_to(address);},
-				Err(_e) => {self.add_error_message(
+			match algorithm {
+				Some(algorithm) => self.hash(algorithm, capture.get(2).is_some()),
+				None => self.add_error_message(
 					WarningLevel::Warning,
-					"Failed to parse given address".to_string())} // handle error if we have a parseInt error
+					format!("Usage: :hash [{}] [copy]", Algorithm::names())
+				)
+			}
+			return;
+		}
+
+		// write a checksum in the file (:fixcrc crc32 0x10..0x1000 0x8 le), or update the saved ones (:fixcrc)
+		let fixcrc_regex = Regex::new(r"^:\s?+fixcrc(\s.*)?$").unwrap();
+		if let Some(capture) = fixcrc_regex.captures(command) {
+			let arguments = capture.get(1).map_or("", |arguments| arguments.as_str());
+			self.fix_checksums(arguments);
+			return;
+		}
+
+		// insert a block of bytes at the cursor (:insert 0x200 00). `:insert` alone switches to Insert mode
+		let insert_regex = Regex::new(r"^:\s?+insert\s+(\S+)(\s+\S+)?\s?+$").unwrap();
+		if let Some(capture) = insert_regex.captures(command) {
+			let pattern = match capture.get(2) {
+				Some(pattern) => parse_hex_bytes(pattern.as_str().trim()),
+				None => Some(vec![0])
+			};
+
+			match (parse_number(&capture[1]), pattern) {
+				(Some(count), Some(pattern)) => self.insert_pattern(count, &pattern),
+				(None, _) => self.add_error_message(WarningLevel::Warning, "Failed to parse the number of bytes".to_string()),
+				(_, None) => self.add_error_message(WarningLevel::Warning, "Invalid hex pattern".to_string())
+			}
+			return;
+		}
+
+		// truncate or extend the file (:resize 0x10000, :resize +0x100 ff, :resize -0x10)
+		let resize_regex = Regex::new(r"^:\s?+resize\s+([+-])?(\S+)(\s+\S+)?\s?+$").unwrap();
+		if let Some(capture) = resize_regex.captures(command) {
+			let len = match parse_number(&capture[2]) {
+				Some(len) => len,
+				None => {
+					self.add_error_message(WarningLevel::Warning, "Failed to parse the length".to_string());
+					return;
+				}
+			};
+
+			let new_size = match capture.get(1).map(|sign| sign.as_str()) {
+				Some("+") => self.file_size.checked_add(len),
+				Some(_) => self.file_size.checked_sub(len),
+				None => Some(len)
+			};
+
+			let pattern = match capture.get(3) {
+				Some(pattern) => parse_hex_bytes(pattern.as_str().trim()),
+				None => Some(vec![0])
+			};
+
+			match (new_size, pattern) {
+				(Some(new_size), Some(pattern)) => self.resize(new_size, &pattern),
+				(None, _) => self.add_error_message(WarningLevel::Warning, "Invalid size".to_string()),
+				(_, None) => self.add_error_message(WarningLevel::Warning, "Invalid hex pattern".to_string())
 			}
 			return;
 		}
@@ -1500,75 +3980,6 @@ impl App {
 			return;
 		}
 
-		// command move cursor forward hexadecimal (:+0x10)
-		let move_cursor_forward_hex_regex = Regex::new(r"^:\s?+\+\s?+0x\d+\s?+$").unwrap();
-		if move_cursor_forward_hex_regex.is_match(command) {
-			// extract search (remove ':+')
-			let search = command.strip_prefix(':').unwrap_or(command);
-			let search = search.trim_start();
-			let search = search.strip_prefix('+').unwrap_or(search);
-			let search = search.trim_start();
-			let search = search.strip_prefix("0x").unwrap_or(search);
-			let search = search.trim();
-
-			if let Ok(num) = u64::from_str_radix(search, 16) {
-				self.cursor_jump_to(self.cursor.saturating_add(num * 2));
-			} else {
-				self.add_error_message(WarningLevel::Warning, "Failed to parse the number".to_string());
-			}
-		}
-
-		// command move cursor forward decimal (:+10)
-		let move_cursor_forward_dec_regex = Regex::new(r"^:\s?+\+\s?+\d+\s?+$").unwrap();
-		if move_cursor_forward_dec_regex.is_match(command) {
-			
-			// extract search (remove ':+')
-			let search = command.strip_prefix(':').unwrap_or(command);
-			let search = search.trim_start();
-			let search = search.strip_prefix('+').unwrap_or(search);
-			let search = search.trim();
-
-			if let Ok(num) = search.parse::<u64>() {
-				self.cursor_jump_to(self.cursor.saturating_add(num * 2));
-			} else {
-				self.add_error_message(WarningLevel::Error, "Failed to parse the number".to_string());
-			}
-		}
-
-		// command move cursor back hexadecimal (:-0x10)
-		let move_cursor_forward_hex_regex = Regex::new(r"^:\s?+-\s?+0x\d+\s?+$").unwrap();
-		if move_cursor_forward_hex_regex.is_match(command) {
-			// extract search (remove ':+')
-			let search = command.strip_prefix(':').unwrap_or(command);
-			let search = search.trim_start();
-			let search = search.strip_prefix('-').unwrap_or(search);
-			let search = search.trim_start();
-			let search = search.strip_prefix("0x").unwrap_or(search);
-			let search = search.trim();
-
-			if let Ok(num) = u64::from_str_radix(search, 16) {
-				self.cursor_jump_to(self.cursor.saturating_sub(num * 2));
-			} else {
-				self.add_error_message(WarningLevel::Warning, "Failed to parse the number".to_string());
-			}
-		}
-
-		// command move cursor back (:-10)
-		let ascii_search_regex = Regex::new(r"^:\s?+-\s?+\d+\s?+$").unwrap();
-		if ascii_search_regex.is_match(command) {
-			// extract search (remove ':-')
-			let search = command.strip_prefix(':').unwrap_or(command);
-			let search = search.trim_start();
-			let search = search.strip_prefix('-').unwrap_or(search);
-			let search = search.trim();
-
-			if let Ok(num) = search.parse::<u64>() {
-				self.cursor_jump_to(self.cursor.saturating_sub(num * 2));
-			} else {
-				self.add_error_message(WarningLevel::Warning, "Failed to parse the number".to_string());
-			}
-		}
-
 		// command is an empty search (:s/abc), cleanup search results
 		let empty_search_regex = Regex::new(r"^:?\s?+/$").unwrap();
 		if empty_search_regex.is_match(command) {
@@ -1587,6 +3998,45 @@ impl App {
 			self.show_infobar = true;
 		}
 
+		// Show the entropy of the file next to the addresses
+		if command == ":show entropy" {
+			self.show_entropy = true;
+		}
+
+		else if command == ":hide entropy" {
+			self.show_entropy = false;
+		}
+
+		else if command == ":entropy" {
+			self.show_entropy = !self.show_entropy;
+		}
+
+		// Show a map of the whole file, that can be clicked
+		if command == ":show minimap" {
+			self.show_minimap = true;
+		}
+
+		else if command == ":hide minimap" {
+			self.show_minimap = false;
+		}
+
+		else if command == ":minimap" {
+			self.show_minimap = !self.show_minimap;
+		}
+
+		// Decode the bytes at the cursor as integers, floats, dates ...
+		if command == ":show inspector" {
+			self.show_inspector = true;
+		}
+
+		else if command == ":hide inspector" {
+			self.show_inspector = false;
+		}
+
+		else if command == ":inspector" {
+			self.show_inspector = !self.show_inspector;
+		}
+
 		// Switch Mode: overwrite, insert
 		if command == ":i" || command == ":insert" || command == ":mode insert" {
 			self.mode = Mode::Insert
@@ -1626,4 +4076,398 @@ impl App {
 		
 
 	}
-}
\ No newline at end of file
+}
+
+/// Values of `.`, `$` and `'a` in the expressions of the commands, as file offsets
+impl Context for App {
+	fn cursor(&self) -> Option<u64> {
+		Some(self.cursor / self.digits())
+	}
+
+	fn file_size(&self) -> Option<u64> {
+		Some(self.file_size)
+	}
+
+	fn mark(&self, name: char) -> Option<u64> {
+		self.marks.get(&name).map(|mark| mark.address)
+	}
+
+	fn read_pointer(&mut self, address: u64) -> Option<u64> {
+		self.read_pointer_at(address, self.follow_format).ok()
+	}
+
+	fn file_offset(&self, address: u64) -> Result<u64, String> {
+		App::file_offset(self, address)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use crate::transform::Xorshift;
+
+	/// Write `content` to a new temporary file and open it
+	fn open(content: &[u8]) -> App {
+		static FILES: AtomicUsize = AtomicUsize::new(0);
+
+		let path = std::env::temp_dir().join(format!(
+			"hextazy-test-{}-{}", std::process::id(), FILES.fetch_add(1, Ordering::Relaxed)
+		));
+		std::fs::write(&path, content).unwrap();
+
+		let app = App::new(path.to_string_lossy().to_string(), Braille::None, None).unwrap();
+		let _ = std::fs::remove_file(&path);
+		app
+	}
+
+	/// Content of the file, with the unsaved changes
+	fn content(app: &mut App) -> Vec<u8> {
+		let mut bytes = vec![];
+		app.read_edited_range(0, app.file_size, |chunk| bytes.extend_from_slice(chunk)).unwrap();
+		bytes
+	}
+
+	/// Select the bytes from `start` to `end` (excluded)
+	fn select(app: &mut App, start: u64, end: u64) {
+		app.selection_start = Some(start * app.digits());
+		app.cursor = end * app.digits() - 1;
+	}
+
+	/// Apply a random edit to `app` and to `model`, a plain vector of the bytes of the file
+	fn random_edit(app: &mut App, model: &mut Vec<u8>, rng: &mut Xorshift) {
+		let random = rng.next_u64();
+		let len = model.len() as u64;
+		let address = (random >> 8) % len;
+		let value = (random >> 16) as u8;
+		let count = ((random >> 24) % 6 + 1) as usize;
+
+		app.selection_start = None;
+		app.cursor = address * app.digits();
+
+		match random % 8 {
+			// type a digit
+			0 => {
+				app.mode = Mode::Overwrite;
+				app.write(address * 2 + 1, value & 0xf);
+				model[address as usize] = (model[address as usize] & 0xf0) | (value & 0xf);
+			},
+			// type a byte in insert mode
+			1 => {
+				app.mode = Mode::Insert;
+				app.write(address * 2, value >> 4);
+				model.insert(address as usize, value & 0xf0);
+			},
+			// delete a byte
+			2 if len > 1 => {
+				app.delete_byte(address);
+				model.remove(address as usize);
+			},
+			// paste in insert mode
+			3 => {
+				let bytes = vec![value; count];
+				app.mode = Mode::Insert;
+				app.paste(&bytes);
+				model.splice(address as usize..address as usize, bytes);
+			},
+			// paste in overwrite mode
+			4 => {
+				let bytes: Vec<u8> = (0..count).map(|i| value.wrapping_add(i as u8)).collect();
+				let end = min(len, address + count as u64);
+				app.mode = Mode::Overwrite;
+				app.paste(&bytes);
+				model.splice(address as usize..end as usize, bytes[..(end - address) as usize].iter().copied());
+			},
+			// delete the selection
+			5 if len > count as u64 => {
+				let end = min(len, address + count as u64);
+				app.mode = Mode::Insert;
+				select(app, address, end);
+				app.delete_selection();
+				model.drain(address as usize..end as usize);
+			},
+			// :move the selection
+			6 => {
+				let end = min(len, address + count as u64);
+				let destination = (random >> 32) % (len + 1);
+				if address < destination && destination < end {
+					return;
+				}
+				select(app, address, end);
+				app.move_block(destination);
+
+				let moved: Vec<u8> = model.drain(address as usize..end as usize).collect();
+				let destination = match destination >= end {
+					true  => destination - (end - address),
+					false => destination
+				};
+				model.splice(destination as usize..destination as usize, moved);
+			},
+			// undo a byte deleted, the same value is inserted back
+			_ => {
+				app.mode = Mode::Insert;
+				app.paste(&[model[address as usize]]);
+				model.insert(address as usize, model[address as usize]);
+			}
+		}
+	}
+
+	#[test]
+	fn edits_match_a_vector() {
+		for seed in 0..40 {
+			let mut rng = Xorshift::new(seed);
+			let original: Vec<u8> = (0..0x40).map(|_| rng.next_u64() as u8 & 0x7).collect();
+
+			let mut app = open(&original);
+			let mut model = original.clone();
+
+			for _ in 0..200 {
+				random_edit(&mut app, &mut model, &mut rng);
+				assert_eq!(content(&mut app), model, "seed {}", seed);
+				assert_eq!(app.file_size, model.len() as u64);
+
+				// going from an address to the next ones gives the same bytes as starting from 0
+				let start = rng.next_u64() % app.file_size;
+				let count = rng.next_u64() % (app.file_size - start);
+				let real_address = app.skip_real_address(app.get_real_address(start), count);
+				assert_eq!(real_address, app.get_real_address(start + count), "seed {}", seed);
+			}
+		}
+	}
+
+	#[test]
+	fn undo_and_redo_every_edit() {
+		for seed in 0..40 {
+			let mut rng = Xorshift::new(seed);
+			let original: Vec<u8> = (0..0x40).map(|_| rng.next_u64() as u8 & 0x7).collect();
+
+			let mut app = open(&original);
+			let mut model = original.clone();
+			let mut states = vec![model.clone()];
+
+			for _ in 0..100 {
+				let history_len = app.history.len();
+				random_edit(&mut app, &mut model, &mut rng);
+
+				if app.history.len() != history_len {
+					states.push(model.clone());
+				}
+			}
+
+			// undo step by step
+			for state in states.iter().rev().skip(1) {
+				app.undo();
+				app.reset();
+				assert_eq!(&content(&mut app), state, "seed {}", seed);
+			}
+			assert!(app.history.is_empty());
+
+			// and redo everything
+			for state in states.iter().skip(1) {
+				app.redo();
+				app.reset();
+				assert_eq!(&content(&mut app), state, "seed {}", seed);
+			}
+		}
+	}
+
+	#[test]
+	fn delete_and_insert_back_leaves_no_changes() {
+		let original: Vec<u8> = (0..=0xff).collect();
+		let mut app = open(&original);
+
+		app.mode = Mode::Insert;
+		select(&mut app, 0x10, 0x30);
+		let deleted = app.delete_selection().unwrap();
+		assert_eq!(deleted, original[0x10..0x30]);
+
+		app.cursor = 0x10 * app.digits();
+		app.paste(&deleted);
+
+		assert_eq!(content(&mut app), original);
+		assert!(app.modified_bytes.is_empty(), "{:?}", app.modified_bytes);
+	}
+
+	#[test]
+	fn move_block_and_undo() {
+		let original: Vec<u8> = (0..0x20).collect();
+		let mut app = open(&original);
+
+		select(&mut app, 2, 6);
+		app.move_block(0x10);
+
+		let mut expected = original.clone();
+		let moved: Vec<u8> = expected.drain(2..6).collect();
+		expected.splice(0xc..0xc, moved.clone());
+		assert_eq!(content(&mut app), expected);
+
+		// the moved bytes are still selected
+		assert_eq!(app.get_selected_bytes(), Some(moved));
+
+		app.undo();
+		assert_eq!(content(&mut app), original);
+		assert!(app.modified_bytes.is_empty(), "{:?}", app.modified_bytes);
+	}
+
+	#[test]
+	fn jump_list_is_bounded() {
+		let mut app = open(&vec![0; 0x10000]);
+
+		for jump in 1..=MAX_JUMPS as u64 + 10 {
+			app.jump_to(jump * 0x100);
+		}
+		assert_eq!(app.jump_list.len(), MAX_JUMPS);
+
+		// going back saves the current position too
+		app.jump_back();
+		assert_eq!(app.jump_list.len(), MAX_JUMPS);
+		assert_eq!(app.cursor / app.digits(), (MAX_JUMPS as u64 + 9) * 0x100);
+
+		app.jump_forward();
+		assert_eq!(app.cursor / app.digits(), (MAX_JUMPS as u64 + 10) * 0x100);
+	}
+
+	#[test]
+	fn pointer_after_the_end_of_the_file() {
+		let mut app = open(&[0x10, 0, 0, 0, 0, 0, 0, 0]);
+
+		assert_eq!(app.read_pointer_at(0, PointerFormat::DEFAULT), Ok(0x10));
+		assert!(app.read_pointer_at(6, PointerFormat::DEFAULT).is_err());
+		assert!(app.read_pointer_at(u64::MAX - 1, PointerFormat::DEFAULT).is_err());
+	}
+
+	#[test]
+	fn go_to_with_a_base_address() {
+		let mut app = open(&vec![0; 0x1000]);
+		app.base_address = 0x100;
+
+		let mut go_to = |input: &str| {
+			app.go_to_expression("", input);
+			app.cursor / app.digits()
+		};
+
+		assert_eq!(go_to("0x1000"), 0xf00);
+
+		// under the base address, the file offsets are written with @
+		assert_eq!(go_to("0x50"), 0xf00);
+		assert_eq!(go_to("@0x50"), 0x50);
+		assert_eq!(go_to("$-0x10"), 0xff0);
+		assert_eq!(go_to("$-0x200"), 0xe00);
+		assert_eq!(go_to("50%"), 0x800);
+		assert_eq!(go_to(".+0x200"), 0xa00);
+	}
+
+	#[test]
+	fn delete_a_big_selection() {
+		let original: Vec<u8> = (0..0x20_0000u32).map(|i| (i * 7 % 251) as u8).collect();
+		let mut app = open(&original);
+
+		// a few changes in the middle of the range
+		app.mode = Mode::Insert;
+		app.cursor = 0x8_0000 * app.digits();
+		app.paste(b"inserted");
+		app.delete_byte(0xc_0000);
+
+		let mut expected = original.clone();
+		expected.splice(0x8_0000..0x8_0000, b"inserted".iter().copied());
+		expected.remove(0xc_0000);
+
+		select(&mut app, 0x4_0000, 0x14_0000);
+		let deleted = app.delete_selection().unwrap();
+		assert_eq!(deleted, expected[0x4_0000..0x14_0000]);
+
+		// a single block in the history
+		assert!(matches!(app.history[app.history.len() - 2], (Modification::DeletedBlock, 0x4_0000, None)));
+
+		let mut after_delete = expected.clone();
+		after_delete.drain(0x4_0000..0x14_0000);
+		assert_eq!(content(&mut app), after_delete);
+
+		app.undo();
+		assert_eq!(content(&mut app), expected);
+
+		app.redo();
+		assert_eq!(content(&mut app), after_delete);
+	}
+
+	#[test]
+	fn resize_in_a_single_step() {
+		let original: Vec<u8> = (0..0x1000u32).map(|i| i as u8).collect();
+		let mut app = open(&original);
+
+		app.resize(0x100, &[0]);
+		assert_eq!(content(&mut app), original[..0x100]);
+		assert!(matches!(app.history[app.history.len() - 2], (Modification::DeletedBlock, 0x100, None)));
+
+		app.undo();
+		assert_eq!(content(&mut app), original);
+		assert!(app.modified_bytes.is_empty());
+
+		// too big, nothing changes
+		app.resize(u64::MAX, &[0]);
+		assert_eq!(app.file_size, 0x1000);
+		assert!(app.history.is_empty());
+	}
+
+	#[test]
+	fn insert_too_many_bytes() {
+		let mut app = open(&[0; 0x10]);
+
+		app.insert_pattern(u64::MAX, &[0xff]);
+		assert_eq!(app.file_size, 0x10);
+		assert!(app.history.is_empty());
+
+		app.insert_pattern(0x100, &[0xff]);
+		assert_eq!(app.file_size, 0x110);
+	}
+
+	#[test]
+	fn file_map_after_edits() {
+		let mut rng = Xorshift::new(7);
+		let original: Vec<u8> = (0..0x10_0000).map(|_| rng.next_u64() as u8).collect();
+		let mut app = open(&original);
+		app.update_file_map();
+
+		app.cursor = 0x4_0000 * app.digits();
+		app.paste(&[0; 0x1_0000]);
+		app.update_file_map();
+		let after_overwrite = app.file_map.as_ref().unwrap().entropies.clone();
+		assert!(after_overwrite[0x100] < stats::LOW_ENTROPY);
+
+		app.mode = Mode::Insert;
+		app.delete_byte(0x100);
+		app.update_file_map();
+		let after_delete = app.file_map.as_ref().unwrap().entropies.clone();
+
+		// same as computing the whole map
+		app.file_map = None;
+		app.update_file_map();
+		assert_eq!(app.file_map.as_ref().unwrap().entropies, after_delete);
+	}
+
+	#[test]
+	fn marks_follow_the_insertions_and_deletions() {
+		let mut app = open(&[0; 0x100]);
+		for (name, address) in [('a', 0x10), ('b', 0x20), ('c', 0x80)] {
+			app.marks.insert(name, Mark { address, label: String::new() });
+		}
+		let mark = |app: &App, name| app.marks[&name].address;
+
+		app.mode = Mode::Insert;
+		app.cursor = 0x18 * app.digits();
+		app.paste(&[1; 0x10]);
+		assert_eq!((mark(&app, 'a'), mark(&app, 'b'), mark(&app, 'c')), (0x10, 0x30, 0x90));
+
+		// the marks on the deleted bytes go to the byte after them
+		select(&mut app, 0x20, 0x40);
+		app.delete_selection();
+		assert_eq!((mark(&app, 'a'), mark(&app, 'b'), mark(&app, 'c')), (0x10, 0x20, 0x70));
+
+		app.delete_byte(0);
+		assert_eq!(mark(&app, 'a'), 0xf);
+
+		app.undo();
+		app.undo();
+		assert_eq!((mark(&app, 'a'), mark(&app, 'c')), (0x10, 0x90));
+	}
+}
@@ -0,0 +1,217 @@
+use std::ops::Range;
+
+/// Encoding of the text pane, set with `--encoding` or `:set encoding`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+	Ascii,		// default, the other bytes are displayed with placeholders or Braille
+	Latin1,
+	Cp437,
+	Cp037,		// EBCDIC
+	Utf8,
+	Utf16Le,
+	Utf16Be
+}
+
+/// What the text pane displays for a byte
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Glyph {
+	Char(char),		// first byte of a character
+	Continuation,	// other bytes of a multi-byte character
+	Invalid			// byte that can't be decoded
+}
+
+impl Encoding {
+	pub fn parse(input: &str) -> Option<Encoding> {
+		match input.trim().to_lowercase().as_str() {
+			"ascii" => Some(Encoding::Ascii),
+			"latin1" | "latin-1" | "iso-8859-1" => Some(Encoding::Latin1),
+			"cp437" => Some(Encoding::Cp437),
+			"cp037" | "ebcdic" => Some(Encoding::Cp037),
+			"utf8" | "utf-8" => Some(Encoding::Utf8),
+			"utf16le" | "utf-16le" => Some(Encoding::Utf16Le),
+			"utf16be" | "utf-16be" => Some(Encoding::Utf16Be),
+			_ => None
+		}
+	}
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			Encoding::Ascii => "ascii",
+			Encoding::Latin1 => "latin1",
+			Encoding::Cp437 => "cp437",
+			Encoding::Cp037 => "cp037",
+			Encoding::Utf8 => "utf8",
+			Encoding::Utf16Le => "utf16le",
+			Encoding::Utf16Be => "utf16be"
+		}
+	}
+
+	/// Number of bytes to read before and after a range to decode the characters that overlap it
+	pub fn context(&self) -> u64 {
+		match self {
+			Encoding::Utf8 | Encoding::Utf16Le | Encoding::Utf16Be => 3,
+			_ => 0
+		}
+	}
+
+	/// Glyphs of the bytes `bytes[range]`. `bytes` starts at the address `address`,
+	/// the bytes around `range` are used to find where the characters start
+	pub fn decode(&self, bytes: &[u8], address: u64, range: Range<usize>) -> Vec<Glyph> {
+		match self {
+			Encoding::Utf8 => {
+				// go back to the first byte of the character at the start of the range
+				let mut start = range.start;
+				while start > 0 && range.start - start < 3 && is_utf8_continuation(bytes[start]) {
+					start -= 1;
+				}
+
+				let glyphs = decode_utf8(&bytes[start..]);
+				glyphs[range.start - start..range.end - start].to_vec()
+			},
+			Encoding::Utf16Le | Encoding::Utf16Be => {
+				// the code units are aligned on the even addresses
+				let mut start = range.start.saturating_sub(((address + range.start as u64) % 2) as usize);
+
+				// start at the high surrogate if we are in the middle of a surrogate pair
+				if start >= 2 && is_low_surrogate(self.code_unit(bytes, start))
+					&& is_high_surrogate(self.code_unit(bytes, start - 2)) {
+					start -= 2;
+				}
+
+				let glyphs = self.decode_utf16(&bytes[start..]);
+				glyphs[range.start - start..range.end - start].to_vec()
+			},
+			_ => bytes[range].iter()
+				.map(|byte| self.decode_byte(*byte))
+				.collect()
+		}
+	}
+
+	/// Glyph of a byte, for the encodings with a single byte per character
+	fn decode_byte(&self, byte: u8) -> Glyph {
+		match self {
+			Encoding::Latin1 => Glyph::Char(byte as char),
+			Encoding::Cp437 if byte >= 0x80 => Glyph::Char(CP437_HIGH[byte as usize - 0x80]),
+			Encoding::Cp037 => Glyph::Char(CP037[byte as usize]),
+			_ if byte.is_ascii() => Glyph::Char(byte as char),
+			_ => Glyph::Invalid
+		}
+	}
+
+	/// Code unit of 16 bits at `bytes[index..index + 2]`, if there are 2 bytes
+	fn code_unit(&self, bytes: &[u8], index: usize) -> Option<u16> {
+		let unit = [*bytes.get(index)?, *bytes.get(index + 1)?];
+
+		match self {
+			Encoding::Utf16Be => Some(u16::from_be_bytes(unit)),
+			_ => Some(u16::from_le_bytes(unit))
+		}
+	}
+
+	fn decode_utf16(&self, bytes: &[u8]) -> Vec<Glyph> {
+		let mut glyphs = Vec::with_capacity(bytes.len());
+
+		while glyphs.len() < bytes.len() {
+			let index = glyphs.len();
+			let unit = self.code_unit(bytes, index);
+			let next_unit = self.code_unit(bytes, index + 2);
+
+			let (c, len) = match (unit, next_unit) {
+				(Some(high), Some(low)) if is_high_surrogate(Some(high)) && is_low_surrogate(Some(low)) => {
+					let code = 0x10000 + ((u32::from(high) - 0xd800) << 10) + (u32::from(low) - 0xdc00);
+					(char::from_u32(code), 4)
+				},
+				(Some(unit), _) => (char::from_u32(u32::from(unit)), 2),
+				(None, _) => (None, 1)	// last odd byte
+			};
+
+			match c {
+				Some(c) => {
+					glyphs.push(Glyph::Char(c));
+					glyphs.extend((1..len).map(|_| Glyph::Continuation));
+				},
+				// unpaired surrogate
+				None => glyphs.extend((0..len).map(|_| Glyph::Invalid))
+			}
+		}
+
+		glyphs
+	}
+
+	/// Bytes of the character `c`, if it can be encoded
+	pub fn encode(&self, c: char) -> Option<Vec<u8>> {
+		match self {
+			Encoding::Ascii if c.is_ascii() => Some(vec![c as u8]),
+			Encoding::Latin1 => u8::try_from(c).ok().map(|byte| vec![byte]),
+			Encoding::Cp437 if c.is_ascii() => Some(vec![c as u8]),
+			Encoding::Cp437 => CP437_HIGH.iter()
+				.position(|high| *high == c)
+				.map(|position| vec![0x80 + position as u8]),
+			Encoding::Cp037 => CP037.iter()
+				.position(|ebcdic| *ebcdic == c)
+				.map(|position| vec![position as u8]),
+			Encoding::Utf8 => Some(c.to_string().into_bytes()),
+			Encoding::Utf16Le => Some(c.encode_utf16(&mut [0; 2]).iter().flat_map(|unit| unit.to_le_bytes()).collect()),
+			Encoding::Utf16Be => Some(c.encode_utf16(&mut [0; 2]).iter().flat_map(|unit| unit.to_be_bytes()).collect()),
+			_ => None
+		}
+	}
+}
+
+fn is_utf8_continuation(byte: u8) -> bool {
+	byte & 0xc0 == 0x80
+}
+
+fn is_high_surrogate(unit: Option<u16>) -> bool {
+	unit.is_some_and(|unit| (0xd800..0xdc00).contains(&unit))
+}
+
+fn is_low_surrogate(unit: Option<u16>) -> bool {
+	unit.is_some_and(|unit| (0xdc00..0xe000).contains(&unit))
+}
+
+fn decode_utf8(bytes: &[u8]) -> Vec<Glyph> {
+	let mut glyphs = Vec::with_capacity(bytes.len());
+
+	for chunk in bytes.utf8_chunks() {
+		for c in chunk.valid().chars() {
+			glyphs.push(Glyph::Char(c));
+			glyphs.extend((1..c.len_utf8()).map(|_| Glyph::Continuation));
+		}
+		glyphs.extend(chunk.invalid().iter().map(|_| Glyph::Invalid));
+	}
+
+	glyphs
+}
+
+/// Characters of CP437 for the bytes 0x80 to 0xff, the first half is ASCII
+static CP437_HIGH: [char; 128] = [
+	'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+	'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+	'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+	'░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+	'└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+	'╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+	'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+	'≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+/// Characters of the EBCDIC code page 037 for the 256 bytes
+static CP037: [char; 256] = [
+	'\u{00}', '\u{01}', '\u{02}', '\u{03}', '\u{9c}', '\u{09}', '\u{86}', '\u{7f}', '\u{97}', '\u{8d}', '\u{8e}', '\u{0b}', '\u{0c}', '\u{0d}', '\u{0e}', '\u{0f}',
+	'\u{10}', '\u{11}', '\u{12}', '\u{13}', '\u{9d}', '\u{85}', '\u{08}', '\u{87}', '\u{18}', '\u{19}', '\u{92}', '\u{8f}', '\u{1c}', '\u{1d}', '\u{1e}', '\u{1f}',
+	'\u{80}', '\u{81}', '\u{82}', '\u{83}', '\u{84}', '\u{0a}', '\u{17}', '\u{1b}', '\u{88}', '\u{89}', '\u{8a}', '\u{8b}', '\u{8c}', '\u{05}', '\u{06}', '\u{07}',
+	'\u{90}', '\u{91}', '\u{16}', '\u{93}', '\u{94}', '\u{95}', '\u{96}', '\u{04}', '\u{98}', '\u{99}', '\u{9a}', '\u{9b}', '\u{14}', '\u{15}', '\u{9e}', '\u{1a}',
+	' ', '\u{a0}', 'â', 'ä', 'à', 'á', 'ã', 'å', 'ç', 'ñ', '¢', '.', '<', '(', '+', '|',
+	'&', 'é', 'ê', 'ë', 'è', 'í', 'î', 'ï', 'ì', 'ß', '!', '$', '*', ')', ';', '¬',
+	'-', '/', 'Â', 'Ä', 'À', 'Á', 'Ã', 'Å', 'Ç', 'Ñ', '¦', ',', '%', '_', '>', '?',
+	'ø', 'É', 'Ê', 'Ë', 'È', 'Í', 'Î', 'Ï', 'Ì', '`', ':', '#', '@', '\'', '=', '"',
+	'Ø', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', '«', '»', 'ð', 'ý', 'þ', '±',
+	'°', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 'ª', 'º', 'æ', '¸', 'Æ', '¤',
+	'µ', '~', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '¡', '¿', 'Ð', 'Ý', 'Þ', '®',
+	'^', '£', '¥', '·', '©', '§', '¶', '¼', '½', '¾', '[', ']', '¯', '¨', '´', '×',
+	'{', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', '\u{ad}', 'ô', 'ö', 'ò', 'ó', 'õ',
+	'}', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', '¹', 'û', 'ü', 'ù', 'ú', 'ÿ',
+	'\\', '÷', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '²', 'Ô', 'Ö', 'Ò', 'Ó', 'Õ',
+	'0', '1', '2', '3', '4', '5', '6', '7', '8', '9', '³', 'Û', 'Ü', 'Ù', 'Ú', '\u{9f}',
+];
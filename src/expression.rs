@@ -0,0 +1,542 @@
+use std::fmt;
+
+/// Byte order of the integers read or written in the file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Endianness {
+	Little,
+	Big
+}
+
+/// Integer read in the file to follow a pointer: `u16le`, `u32be`, `u64le` ...
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointerFormat {
+	pub size: usize,	// in bytes
+	pub endianness: Endianness
+}
+
+impl PointerFormat {
+	pub const DEFAULT: PointerFormat = PointerFormat { size: 4, endianness: Endianness::Little };
+
+	/// Parse `u16`, `u32` or `u64`, followed by `le` (the default) or `be`
+	pub fn parse(input: &str) -> Option<PointerFormat> {
+		let input = input.to_lowercase();
+
+		let (size, endianness) = match input.len() {
+			3 => (&input[..], Endianness::Little),
+			5 if input.ends_with("le") => (&input[..3], Endianness::Little),
+			5 if input.ends_with("be") => (&input[..3], Endianness::Big),
+			_ => return None
+		};
+
+		let size = match size {
+			"u16" => 2,
+			"u32" => 4,
+			"u64" => 8,
+			_ => return None
+		};
+
+		Some(PointerFormat { size, endianness })
+	}
+
+	/// Value of the integer stored in `bytes`
+	pub fn decode(&self, bytes: &[u8]) -> u64 {
+		let mut value_bytes = [0u8; 8];
+
+		match self.endianness {
+			Endianness::Little => {
+				value_bytes[..bytes.len()].copy_from_slice(bytes);
+				u64::from_le_bytes(value_bytes)
+			},
+			Endianness::Big => {
+				value_bytes[8 - bytes.len()..].copy_from_slice(bytes);
+				u64::from_be_bytes(value_bytes)
+			}
+		}
+	}
+}
+
+impl fmt::Display for PointerFormat {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let endianness = match self.endianness {
+			Endianness::Little => "le",
+			Endianness::Big => "be"
+		};
+
+		write!(f, "u{}{}", self.size * 8, endianness)
+	}
+}
+
+/// Values that can be used in an expression, besides the numbers
+pub trait Context {
+	/// Address of the cursor: `.`
+	fn cursor(&self) -> Option<u64>;
+	/// Size of the file: `$`, also used for the percentages
+	fn file_size(&self) -> Option<u64>;
+	/// Address of a mark: `'a`
+	fn mark(&self, name: char) -> Option<u64>;
+	/// Integer stored at the file offset `address`: `[address]`
+	fn read_pointer(&mut self, address: u64) -> Option<u64>;
+	/// File offset of a display address, written with numbers and pointers.
+	/// Fails if the address is not in the file
+	fn file_offset(&self, address: u64) -> Result<u64, String>;
+}
+
+/// Context for the expressions that don't depend on the file, i.e `0x10*4`
+pub struct NoContext;
+
+impl Context for NoContext {
+	fn cursor(&self) -> Option<u64> { None }
+	fn file_size(&self) -> Option<u64> { None }
+	fn mark(&self, _name: char) -> Option<u64> { None }
+	fn read_pointer(&mut self, _address: u64) -> Option<u64> { None }
+	fn file_offset(&self, address: u64) -> Result<u64, String> { Ok(address) }
+}
+
+/// Evaluate an expression like `$-0x200`, `.+4*0x20`, `'a+50%` or `[0x3c]`. The result can't be negative
+pub fn evaluate(input: &str, context: &mut impl Context) -> Result<u64, String> {
+	let value = evaluate_signed(input, context)?;
+
+	u64::try_from(value).map_err(|_| format!("{} is not a valid address", value))
+}
+
+/// Parse a number given in a command, i.e `0x1f`, `31` or `0x10*4`
+pub fn parse_number(input: &str) -> Option<u64> {
+	evaluate(input, &mut NoContext).ok()
+}
+
+/// Evaluate an expression, the result can be negative
+pub fn evaluate_signed(input: &str, context: &mut impl Context) -> Result<i128, String> {
+	evaluate_with_positions(input, context).map(|(value, _)| value)
+}
+
+/// Evaluate an expression giving an address in the file. If it is only made of numbers and pointers,
+/// i.e `0x08001000` or `[0x3c]`, it is a display address and is converted with `context.file_offset()`.
+/// The positions `$`, `.`, `'a`, the percentages and the offsets written `@0x50` are already file offsets,
+/// so `$-0x200` is not converted
+pub fn evaluate_address(input: &str, context: &mut impl Context) -> Result<i128, String> {
+	let (value, positions_used) = evaluate_with_positions(input, context)?;
+
+	match u64::try_from(value) {
+		Ok(address) if !positions_used => context.file_offset(address).map(i128::from),
+		_ => Ok(value)
+	}
+}
+
+/// Same as evaluate_address(), the file offset can't be negative
+pub fn evaluate_offset(input: &str, context: &mut impl Context) -> Result<u64, String> {
+	let value = evaluate_address(input, context)?;
+
+	u64::try_from(value).map_err(|_| format!("{} is not a valid address", value))
+}
+
+/// Evaluate a range of addresses, i.e `0x100..0x200` or `@0..$`, with evaluate_offset().
+/// Return (start, end), the end is excluded from the range
+pub fn evaluate_range(input: &str, context: &mut impl Context) -> Result<(u64, u64), String> {
+	let (start, end) = input.split_once("..").ok_or(format!("{} is not a range, i.e 0x100..0x200", input))?;
+
+	Ok((evaluate_offset(start, context)?, evaluate_offset(end, context)?))
+}
+
+/// Evaluate an expression, and tell if it uses a position in the file (`$`, `.`, a mark or a percentage)
+fn evaluate_with_positions(input: &str, context: &mut impl Context) -> Result<(i128, bool), String> {
+	let mut parser = Parser { chars: input.chars().collect(), position: 0, positions_used: false, context };
+
+	let value = parser.expression()?;
+
+	parser.skip_spaces();
+	match parser.peek() {
+		None => Ok((value, parser.positions_used)),
+		Some(c) => Err(format!("Unexpected '{}' in the expression", c))
+	}
+}
+
+/// Recursive descent parser. From the lowest to the highest precedence:
+/// `|`, `&`, `<< >>`, `+ -`, `* / %`, unary `- + @`, then numbers, `$`, `.`, marks, parentheses
+/// and pointers `[...]`
+struct Parser<'a, C: Context> {
+	chars: Vec<char>,
+	position: usize,
+	positions_used: bool,	// `$`, `.`, a mark, a percentage or `@` was used, see `evaluate_address()`
+	context: &'a mut C
+}
+
+impl<C: Context> Parser<'_, C> {
+	fn peek(&self) -> Option<char> {
+		self.chars.get(self.position).copied()
+	}
+
+	fn skip_spaces(&mut self) {
+		while self.peek().is_some_and(|c| c.is_whitespace()) {
+			self.position += 1;
+		}
+	}
+
+	/// Consume `operator` if it is the next token
+	fn accept(&mut self, operator: &str) -> bool {
+		self.skip_spaces();
+
+		let matches = operator.chars()
+			.enumerate()
+			.all(|(i, c)| self.chars.get(self.position + i) == Some(&c));
+
+		if matches {
+			self.position += operator.len();
+		}
+		matches
+	}
+
+	fn expression(&mut self) -> Result<i128, String> {
+		let mut value = self.and()?;
+
+		while self.accept("|") {
+			value |= self.and()?;
+		}
+		Ok(value)
+	}
+
+	fn and(&mut self) -> Result<i128, String> {
+		let mut value = self.shift()?;
+
+		while self.accept("&") {
+			value &= self.shift()?;
+		}
+		Ok(value)
+	}
+
+	fn shift(&mut self) -> Result<i128, String> {
+		let mut value = self.sum()?;
+
+		loop {
+			if self.accept("<<") {
+				let bits = self.sum()?;
+				value = u32::try_from(bits).ok()
+					.and_then(|bits| value.checked_mul(1i128.checked_shl(bits)?))
+					.ok_or("The shift overflows")?;
+			} else if self.accept(">>") {
+				let bits = self.sum()?;
+				value = u32::try_from(bits).ok()
+					.map(|bits| value.checked_shr(bits).unwrap_or(0))
+					.ok_or("Invalid shift")?;
+			} else {
+				return Ok(value);
+			}
+		}
+	}
+
+	fn sum(&mut self) -> Result<i128, String> {
+		let mut value = self.product()?;
+
+		loop {
+			if self.accept("+") {
+				value = value.checked_add(self.product()?).ok_or("The addition overflows")?;
+			} else if self.accept("-") {
+				value = value.checked_sub(self.product()?).ok_or("The subtraction overflows")?;
+			} else {
+				return Ok(value);
+			}
+		}
+	}
+
+	fn product(&mut self) -> Result<i128, String> {
+		let mut value = self.unary()?;
+
+		loop {
+			if self.accept("*") {
+				value = value.checked_mul(self.unary()?).ok_or("The multiplication overflows")?;
+			} else if self.accept("/") {
+				value = value.checked_div(self.unary()?).ok_or("Division by zero")?;
+			} else if self.accept("%") {
+				value = value.checked_rem(self.unary()?).ok_or("Division by zero")?;
+			} else {
+				return Ok(value);
+			}
+		}
+	}
+
+	fn unary(&mut self) -> Result<i128, String> {
+		if self.accept("-") {
+			return Ok(self.unary()?.checked_neg().ok_or("The negation overflows")?);
+		}
+		if self.accept("+") {
+			return self.unary();
+		}
+		// `@0x50` is the file offset 0x50, it is not converted from a display address
+		if self.accept("@") {
+			let value = self.unary()?;
+			self.positions_used = true;
+			return Ok(value);
+		}
+
+		let value = self.primary()?;
+
+		// `50%` is a percentage of the file, `50%3` is a modulo
+		if self.is_percentage() {
+			self.position += 1;
+			self.positions_used = true;
+
+			let file_size = self.context.file_size().ok_or("Percentages can't be used here")?;
+			return value.checked_mul(file_size.into())
+				.map(|value| value / 100)
+				.ok_or("The percentage overflows".to_string());
+		}
+
+		Ok(value)
+	}
+
+	/// `%` is a percentage if it is not followed by a value
+	fn is_percentage(&mut self) -> bool {
+		self.skip_spaces();
+
+		if self.peek() != Some('%') {
+			return false;
+		}
+
+		let next = self.chars[self.position + 1..].iter().find(|c| !c.is_whitespace());
+		matches!(next, None | Some(')' | '+' | '-' | '*' | '/' | '%' | '&' | '|' | '<' | '>'))
+	}
+
+	fn primary(&mut self) -> Result<i128, String> {
+		self.skip_spaces();
+
+		match self.peek() {
+			Some('(') => {
+				self.position += 1;
+				let value = self.expression()?;
+
+				if !self.accept(")") {
+					return Err("Missing ')' in the expression".to_string());
+				}
+				Ok(value)
+			},
+			// read the pointer stored at an address
+			Some('[') => {
+				self.position += 1;
+
+				// the address of the pointer is converted like the result of evaluate_address()
+				let positions_used = std::mem::replace(&mut self.positions_used, false);
+				let address = self.expression()?;
+				let address_is_offset = std::mem::replace(&mut self.positions_used, positions_used);
+
+				if !self.accept("]") {
+					return Err("Missing ']' in the expression".to_string());
+				}
+
+				let address = u64::try_from(address).map_err(|_| format!("Can't read a pointer at {}", address))?;
+				let address = match address_is_offset {
+					true => address,
+					false => self.context.file_offset(address)?
+				};
+
+				self.context.read_pointer(address)
+					.map(i128::from)
+					.ok_or(format!("Can't read a pointer at 0x{:x}", address))
+			},
+			Some('$') => {
+				self.position += 1;
+				self.positions_used = true;
+				self.context.file_size().map(i128::from).ok_or("`$` can't be used here".to_string())
+			},
+			Some('.') => {
+				self.position += 1;
+				self.positions_used = true;
+				self.context.cursor().map(i128::from).ok_or("`.` can't be used here".to_string())
+			},
+			Some('\'') => {
+				self.position += 1;
+				let name = self.peek().ok_or("Missing the name of the mark after '")?;
+				self.position += 1;
+				self.positions_used = true;
+
+				self.context.mark(name).map(i128::from).ok_or(format!("Mark '{}' is not set", name))
+			},
+			Some(c) if c.is_ascii_digit() => self.number(),
+			Some(c) => Err(format!("Unexpected '{}' in the expression", c)),
+			None => Err("The expression is incomplete".to_string())
+		}
+	}
+
+	/// Numbers: `1234`, `0x4d2`, `0o2322` or `0b10011010010`
+	fn number(&mut self) -> Result<i128, String> {
+		let start = self.position;
+
+		while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+			self.position += 1;
+		}
+
+		let literal: String = self.chars[start..self.position].iter()
+			.filter(|c| **c != '_')
+			.collect();
+		let lowercase = literal.to_lowercase();
+
+		let (digits, radix) = match lowercase.get(..2) {
+			Some("0x") => (&lowercase[2..], 16),
+			Some("0o") => (&lowercase[2..], 8),
+			Some("0b") => (&lowercase[2..], 2),
+			_ => (lowercase.as_str(), 10)
+		};
+
+		u64::from_str_radix(digits, radix)
+			.map(i128::from)
+			.map_err(|_| format!("Invalid number {}", literal))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// File of 0x1000 bytes displayed from the base address 0x100, with the cursor at 0x20,
+	/// the mark `a` at 0x40, and the u32le pointer 0x200 stored at 0x10
+	struct TestContext;
+
+	impl Context for TestContext {
+		fn cursor(&self) -> Option<u64> { Some(0x20) }
+		fn file_size(&self) -> Option<u64> { Some(0x1000) }
+		fn mark(&self, name: char) -> Option<u64> { (name == 'a').then_some(0x40) }
+		fn read_pointer(&mut self, address: u64) -> Option<u64> { (address == 0x10).then_some(0x200) }
+		fn file_offset(&self, address: u64) -> Result<u64, String> {
+			address.checked_sub(0x100).ok_or(format!("0x{:x} is before the base address", address))
+		}
+	}
+
+	fn eval(input: &str) -> Result<i128, String> {
+		evaluate_signed(input, &mut TestContext)
+	}
+
+	#[test]
+	fn numbers() {
+		assert_eq!(eval("1234"), Ok(1234));
+		assert_eq!(eval("0x4d2"), Ok(1234));
+		assert_eq!(eval("0X4D2"), Ok(1234));
+		assert_eq!(eval("0o2322"), Ok(1234));
+		assert_eq!(eval("0b100_1101_0010"), Ok(1234));
+		assert_eq!(eval("0xffff_ffff_ffff_ffff"), Ok(u64::MAX.into()));
+	}
+
+	#[test]
+	fn precedence() {
+		assert_eq!(eval("1+2*3"), Ok(7));
+		assert_eq!(eval("(1+2)*3"), Ok(9));
+		assert_eq!(eval("0x10-4-4"), Ok(8));
+		assert_eq!(eval("20/3*3"), Ok(18));
+		assert_eq!(eval("1<<2+1"), Ok(8));
+		assert_eq!(eval("0x100>>4"), Ok(0x10));
+		assert_eq!(eval("1|2&3"), Ok(3));
+		assert_eq!(eval("0xf0&0x3c|1"), Ok(0x31));
+		assert_eq!(eval(" 2 * ( 3 + 4 ) "), Ok(14));
+	}
+
+	#[test]
+	fn unary_minus() {
+		assert_eq!(eval("-4+10"), Ok(6));
+		assert_eq!(eval("--4"), Ok(4));
+		assert_eq!(eval("+4"), Ok(4));
+		assert_eq!(eval("-(2*3)"), Ok(-6));
+		assert_eq!(eval("2*-3"), Ok(-6));
+
+		// the result of evaluate() is an address, it can't be negative
+		assert!(evaluate("4-5", &mut TestContext).is_err());
+		assert_eq!(evaluate("5-4", &mut TestContext), Ok(1));
+	}
+
+	#[test]
+	fn percentage_and_modulo() {
+		assert_eq!(eval("50%"), Ok(0x800));
+		assert_eq!(eval("25% + 4"), Ok(0x404));
+		assert_eq!(eval("(10)%"), Ok(0x199));
+		assert_eq!(eval("10 % 3"), Ok(1));
+		assert_eq!(eval("50%%3"), Ok(0x800 % 3));
+		assert_eq!(parse_number("50%"), None);
+	}
+
+	#[test]
+	fn positions_and_marks() {
+		assert_eq!(eval("$"), Ok(0x1000));
+		assert_eq!(eval("$-0x200"), Ok(0xe00));
+		assert_eq!(eval("."), Ok(0x20));
+		assert_eq!(eval(".+4*0x20"), Ok(0xa0));
+		assert_eq!(eval("'a"), Ok(0x40));
+		assert_eq!(eval("'a+50%"), Ok(0x840));
+
+		assert_eq!(eval("'b"), Err("Mark 'b' is not set".to_string()));
+		assert_eq!(eval("'"), Err("Missing the name of the mark after '".to_string()));
+		assert_eq!(parse_number("$"), None);
+		assert_eq!(parse_number("."), None);
+	}
+
+	#[test]
+	fn pointers() {
+		assert_eq!(eval("[0x110]"), Ok(0x200));
+		assert_eq!(eval("[0x108+8]+2"), Ok(0x202));
+		assert_eq!(eval("[.-0x10]"), Ok(0x200));
+		assert_eq!(eval("[@0x10]"), Ok(0x200));
+		assert_eq!(eval("[0x1100]"), Err("Can't read a pointer at 0x1000".to_string()));
+		assert_eq!(eval("[4"), Err("Missing ']' in the expression".to_string()));
+		assert_eq!(parse_number("[0x10]"), None);
+	}
+
+	#[test]
+	fn display_addresses() {
+		let address = |input| evaluate_address(input, &mut TestContext);
+
+		// only the numbers and the pointers are display addresses
+		assert_eq!(address("0x1100"), Ok(0x1000));
+		assert_eq!(address("0x1000+0x100"), Ok(0x1000));
+		assert!(address("0x50").is_err());
+		assert_eq!(address("@0x50"), Ok(0x50));
+		assert_eq!(address("@0x50+0x10"), Ok(0x60));
+		assert_eq!(address("[@0x10]"), Ok(0x100));
+		assert!(address("[0x10]").is_err());
+		assert_eq!(address("[0x110]"), Ok(0x100));
+		assert_eq!(address("$-0x10"), Ok(0xff0));
+		assert_eq!(address("$-0x200"), Ok(0xe00));
+		assert_eq!(address("50%"), Ok(0x800));
+		assert_eq!(address(".+0x200"), Ok(0x220));
+		assert_eq!(address("'a+[0x110]"), Ok(0x240));
+		assert_eq!(address("[.-0x10]"), Ok(0x100));
+
+		// the relative moves are not converted
+		assert_eq!(eval("0x1100"), Ok(0x1100));
+	}
+
+	#[test]
+	fn errors() {
+		assert_eq!(eval(""), Err("The expression is incomplete".to_string()));
+		assert_eq!(eval("1+"), Err("The expression is incomplete".to_string()));
+		assert_eq!(eval("(1+2"), Err("Missing ')' in the expression".to_string()));
+		assert_eq!(eval("1 2"), Err("Unexpected '2' in the expression".to_string()));
+		assert_eq!(eval("x"), Err("Unexpected 'x' in the expression".to_string()));
+		assert_eq!(eval("0xzz"), Err("Invalid number 0xzz".to_string()));
+		assert_eq!(eval("0x1_0000_0000_0000_0000"), Err("Invalid number 0x10000000000000000".to_string()));
+		assert_eq!(eval("1/0"), Err("Division by zero".to_string()));
+		assert_eq!(eval("1%0"), Err("Division by zero".to_string()));
+		assert_eq!(eval("1<<200"), Err("The shift overflows".to_string()));
+		assert_eq!(eval("0xffffffffffffffff*0xffffffffffffffff"), Err("The multiplication overflows".to_string()));
+		assert_eq!(eval("-(-(1<<126)*2)"), Err("The negation overflows".to_string()));
+	}
+
+	#[test]
+	fn ranges() {
+		assert_eq!(evaluate_range("0x10..0x20", &mut NoContext), Ok((0x10, 0x20)));
+		assert_eq!(evaluate_range("0x10..4*8", &mut NoContext), Ok((0x10, 0x20)));
+		assert!(evaluate_range("0x10", &mut NoContext).is_err());
+		assert!(evaluate_range("0x10..$", &mut NoContext).is_err());
+
+		// display addresses and file offsets
+		assert_eq!(evaluate_range("0x110..$", &mut TestContext), Ok((0x10, 0x1000)));
+		assert_eq!(evaluate_range("@0..@0x10", &mut TestContext), Ok((0, 0x10)));
+	}
+
+	#[test]
+	fn pointer_formats() {
+		let format = PointerFormat::parse("u16be").unwrap();
+		assert_eq!(format, PointerFormat { size: 2, endianness: Endianness::Big });
+		assert_eq!(format.decode(&[0x12, 0x34]), 0x1234);
+		assert_eq!(format.to_string(), "u16be");
+
+		assert_eq!(PointerFormat::parse("u32"), Some(PointerFormat::DEFAULT));
+		assert_eq!(PointerFormat::DEFAULT.decode(&[0x78, 0x56, 0x34, 0x12]), 0x12345678);
+		assert_eq!(PointerFormat::parse("u8"), None);
+		assert_eq!(PointerFormat::parse("u32me"), None);
+	}
+}
@@ -0,0 +1,652 @@
+use std::fmt;
+
+use crate::expression::{evaluate_offset, evaluate_range, Context, Endianness};
+
+/// Checksums and hashes that can be computed with `:hash` and `:fixcrc`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algorithm {
+	Crc16,		// CRC-16/ARC
+	Crc16Ccitt,	// CRC-16/CCITT-FALSE
+	Crc16Xmodem,	// CRC-16/XMODEM
+	Crc32,		// CRC-32 used by zip, png, ethernet ...
+	Adler32,
+	Md5,
+	Sha1,
+	Sha256,
+	Sha512
+}
+
+impl Algorithm {
+	pub const ALL: [Algorithm; 9] = [
+		Algorithm::Crc16, Algorithm::Crc16Ccitt, Algorithm::Crc16Xmodem, Algorithm::Crc32,
+		Algorithm::Adler32, Algorithm::Md5, Algorithm::Sha1, Algorithm::Sha256, Algorithm::Sha512
+	];
+
+	/// Parse the name of an algorithm, i.e `crc32` or `sha256`
+	pub fn parse(name: &str) -> Option<Algorithm> {
+		let name = name.to_lowercase().replace(['-', '_'], "");
+
+		Algorithm::ALL.into_iter()
+			.find(|algorithm| algorithm.name().replace('-', "") == name)
+	}
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			Algorithm::Crc16 => "crc16",
+			Algorithm::Crc16Ccitt => "crc16-ccitt",
+			Algorithm::Crc16Xmodem => "crc16-xmodem",
+			Algorithm::Crc32 => "crc32",
+			Algorithm::Adler32 => "adler32",
+			Algorithm::Md5 => "md5",
+			Algorithm::Sha1 => "sha1",
+			Algorithm::Sha256 => "sha256",
+			Algorithm::Sha512 => "sha512"
+		}
+	}
+
+	/// Names of all the algorithms, used in the error messages
+	pub fn names() -> String {
+		Algorithm::ALL.map(|algorithm| algorithm.name()).join("|")
+	}
+
+	/// Whether the result is an integer (crc, adler), written in little or big endian by `:fixcrc`.
+	/// The hashes are always written in the order of their digest
+	pub fn is_integer(&self) -> bool {
+		matches!(self, Algorithm::Crc16 | Algorithm::Crc16Ccitt | Algorithm::Crc16Xmodem | Algorithm::Crc32 | Algorithm::Adler32)
+	}
+
+	/// Create a Hasher to compute this checksum / hash
+	pub fn hasher(&self) -> Hasher {
+		match self {
+			Algorithm::Crc16 => Hasher::Crc16(0),
+			Algorithm::Crc16Ccitt => Hasher::Crc16Ccitt(0xffff),
+			Algorithm::Crc16Xmodem => Hasher::Crc16Xmodem(0),
+			Algorithm::Crc32 => Hasher::Crc32(0xffffffff),
+			Algorithm::Adler32 => Hasher::Adler32(1, 0),
+			Algorithm::Md5 => Hasher::Md5(Blocks::new(), MD5_INIT),
+			Algorithm::Sha1 => Hasher::Sha1(Blocks::new(), SHA1_INIT),
+			Algorithm::Sha256 => Hasher::Sha256(Blocks::new(), SHA256_INIT),
+			Algorithm::Sha512 => Hasher::Sha512(Blocks::new(), SHA512_INIT)
+		}
+	}
+}
+
+/// Hash of the selection displayed in the preview pane, computed by `app.update_preview_digest()`.
+/// It is computed again only when the selection or `app.changes_count` changed
+pub struct SelectionDigest {
+	pub algorithm: Algorithm,
+	pub start: u64,
+	pub end: u64,
+	pub changes_count: u64,
+	pub digest: Vec<u8>
+}
+
+/// A checksum written into the file by `:fixcrc`, i.e `crc32 0x10..0x1000 0x8 le`.
+/// The presets are remembered for each file, to update the checksums after each patch
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChecksumPreset {
+	pub algorithm: Algorithm,
+	pub start: u64,
+	pub end: u64,		// excluded from the range
+	pub destination: u64,
+	pub endianness: Option<Endianness>	// None for the hashes, written in the order of their digest
+}
+
+impl ChecksumPreset {
+	/// Parse the arguments of `:fixcrc`: `<algorithm> <range> <destination> [le|be]`.
+	/// The addresses are evaluated with `context`, see `expression::evaluate_address()`
+	pub fn parse(arguments: &str, context: &mut impl Context) -> Result<ChecksumPreset, String> {
+		let usage = format!("Usage: :fixcrc <{}> <start..end> <destination> [le|be]", Algorithm::names());
+
+		let arguments: Vec<&str> = arguments.split_whitespace().collect();
+
+		let (algorithm, range, destination, endianness) = match arguments[..] {
+			[algorithm, range, destination] => (algorithm, range, destination, None),
+			[algorithm, range, destination, endianness] => (algorithm, range, destination, Some(endianness)),
+			_ => return Err(usage)
+		};
+
+		let algorithm = Algorithm::parse(algorithm)
+			.ok_or(format!("Unknown algorithm {}. {}", algorithm, usage))?;
+		let (start, end) = evaluate_range(range, context)
+			.map_err(|message| format!("Failed to parse the range {}: {}", range, message))?;
+		let destination = evaluate_offset(destination, context)
+			.map_err(|message| format!("Failed to parse the destination {}: {}", destination, message))?;
+		let endianness = match (algorithm.is_integer(), endianness) {
+			(true, None | Some("le")) => Some(Endianness::Little),
+			(true, Some("be")) => Some(Endianness::Big),
+			(false, None) => None,
+			(false, Some("le" | "be")) => return Err(format!("{} is written in the order of its digest, without le or be", algorithm.name())),
+			_ => return Err(usage)
+		};
+
+		Ok(ChecksumPreset { algorithm, start, end, destination, endianness })
+	}
+
+	/// Bytes to write in the file for the checksum `digest`, returned by `Hasher::finalize()`
+	pub fn encode(&self, digest: &[u8]) -> Vec<u8> {
+		match self.endianness {
+			Some(Endianness::Little) => digest.iter().rev().copied().collect(),
+			Some(Endianness::Big) | None => digest.to_vec()
+		}
+	}
+}
+
+impl fmt::Display for ChecksumPreset {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{} 0x{:x}..0x{:x} 0x{:x}", self.algorithm.name(), self.start, self.end, self.destination)?;
+
+		match self.endianness {
+			Some(Endianness::Little) => write!(f, " le"),
+			Some(Endianness::Big) => write!(f, " be"),
+			None => Ok(())
+		}
+	}
+}
+
+/// Compute a checksum or a hash over data given in several chunks:
+/// call `update()` for each chunk, then `finalize()`
+pub enum Hasher {
+	Crc16(u16),
+	Crc16Ccitt(u16),
+	Crc16Xmodem(u16),
+	Crc32(u32),
+	Adler32(u32, u32),
+	Md5(Blocks<64>, [u32; 4]),
+	Sha1(Blocks<64>, [u32; 5]),
+	Sha256(Blocks<64>, [u32; 8]),
+	Sha512(Blocks<128>, [u64; 8])
+}
+
+impl Hasher {
+	pub fn update(&mut self, data: &[u8]) {
+		match self {
+			Hasher::Crc16(crc) => {
+				for byte in data {
+					*crc = (*crc >> 8) ^ CRC16_ARC_TABLE[((*crc ^ *byte as u16) & 0xff) as usize];
+				}
+			},
+			Hasher::Crc16Ccitt(crc) | Hasher::Crc16Xmodem(crc) => {
+				for byte in data {
+					*crc = (*crc << 8) ^ CRC16_CCITT_TABLE[((*crc >> 8) ^ *byte as u16) as usize];
+				}
+			},
+			Hasher::Crc32(crc) => {
+				for byte in data {
+					*crc = (*crc >> 8) ^ CRC32_TABLE[((*crc ^ *byte as u32) & 0xff) as usize];
+				}
+			},
+			Hasher::Adler32(a, b) => {
+				// the sums can't overflow before 5552 bytes
+				for chunk in data.chunks(5552) {
+					for byte in chunk {
+						*a += *byte as u32;
+						*b += *a;
+					}
+					*a %= 65521;
+					*b %= 65521;
+				}
+			},
+			Hasher::Md5(blocks, state) => blocks.update(data, |block| md5_compress(state, block)),
+			Hasher::Sha1(blocks, state) => blocks.update(data, |block| sha1_compress(state, block)),
+			Hasher::Sha256(blocks, state) => blocks.update(data, |block| sha256_compress(state, block)),
+			Hasher::Sha512(blocks, state) => blocks.update(data, |block| sha512_compress(state, block))
+		}
+	}
+
+	/// Return the checksum / hash. The checksums are given in big endian, like they are usually displayed
+	pub fn finalize(self) -> Vec<u8> {
+		match self {
+			Hasher::Crc16(crc) | Hasher::Crc16Ccitt(crc) | Hasher::Crc16Xmodem(crc) => crc.to_be_bytes().to_vec(),
+			Hasher::Crc32(crc) => (crc ^ 0xffffffff).to_be_bytes().to_vec(),
+			Hasher::Adler32(a, b) => ((b << 16) | a).to_be_bytes().to_vec(),
+			Hasher::Md5(mut blocks, mut state) => {
+				blocks.pad(8, false, |block| md5_compress(&mut state, block));
+				state.iter().flat_map(|word| word.to_le_bytes()).collect()
+			},
+			Hasher::Sha1(mut blocks, mut state) => {
+				blocks.pad(8, true, |block| sha1_compress(&mut state, block));
+				state.iter().flat_map(|word| word.to_be_bytes()).collect()
+			},
+			Hasher::Sha256(mut blocks, mut state) => {
+				blocks.pad(8, true, |block| sha256_compress(&mut state, block));
+				state.iter().flat_map(|word| word.to_be_bytes()).collect()
+			},
+			Hasher::Sha512(mut blocks, mut state) => {
+				blocks.pad(16, true, |block| sha512_compress(&mut state, block));
+				state.iter().flat_map(|word| word.to_be_bytes()).collect()
+			}
+		}
+	}
+}
+
+/// Split the data given to MD5 and SHA into blocks of `N` bytes
+pub struct Blocks<const N: usize> {
+	buffer: [u8; N],
+	used: usize,	// number of bytes in the buffer
+	length: u128	// number of bytes hashed
+}
+
+impl<const N: usize> Blocks<N> {
+	fn new() -> Blocks<N> {
+		Blocks { buffer: [0; N], used: 0, length: 0 }
+	}
+
+	fn update(&mut self, data: &[u8], mut compress: impl FnMut(&[u8; N])) {
+		self.length += data.len() as u128;
+
+		for byte in data {
+			self.buffer[self.used] = *byte;
+			self.used += 1;
+
+			if self.used == N {
+				compress(&self.buffer);
+				self.used = 0;
+			}
+		}
+	}
+
+	/// Add the padding of MD5 and SHA: a 1 bit, zeros, then the length in bits
+	/// written on `length_size` bytes
+	fn pad(&mut self, length_size: usize, big_endian: bool, mut compress: impl FnMut(&[u8; N])) {
+		let length_in_bits = self.length * 8;
+
+		let mut padding = vec![0x80];
+		let padded_len = (self.used + 1 + length_size).next_multiple_of(N);
+		padding.resize(padded_len - self.used - length_size, 0);
+
+		let length_bytes = match big_endian {
+			true => length_in_bits.to_be_bytes(),
+			false => length_in_bits.to_le_bytes()
+		};
+		match big_endian {
+			true => padding.extend_from_slice(&length_bytes[16 - length_size..]),
+			false => padding.extend_from_slice(&length_bytes[..length_size])
+		}
+
+		self.update(&padding, &mut compress);
+	}
+}
+
+const fn crc_table_reflected(polynomial: u32) -> [u32; 256] {
+	let mut table = [0; 256];
+	let mut i = 0;
+
+	while i < 256 {
+		let mut crc = i as u32;
+		let mut bit = 0;
+		while bit < 8 {
+			crc = match crc & 1 {
+				1 => (crc >> 1) ^ polynomial,
+				_ => crc >> 1
+			};
+			bit += 1;
+		}
+		table[i] = crc;
+		i += 1;
+	}
+
+	table
+}
+
+const fn crc16_table(polynomial: u16) -> [u16; 256] {
+	let mut table = [0; 256];
+	let mut i = 0;
+
+	while i < 256 {
+		let mut crc = (i as u16) << 8;
+		let mut bit = 0;
+		while bit < 8 {
+			crc = match crc & 0x8000 {
+				0 => crc << 1,
+				_ => (crc << 1) ^ polynomial
+			};
+			bit += 1;
+		}
+		table[i] = crc;
+		i += 1;
+	}
+
+	table
+}
+
+const CRC32_TABLE: [u32; 256] = crc_table_reflected(0xedb88320);
+const CRC16_CCITT_TABLE: [u16; 256] = crc16_table(0x1021);
+const CRC16_ARC_TABLE: [u16; 256] = {
+	let table = crc_table_reflected(0xa001);
+	let mut table_u16 = [0; 256];
+	let mut i = 0;
+	while i < 256 {
+		table_u16[i] = table[i] as u16;
+		i += 1;
+	}
+	table_u16
+};
+
+const MD5_INIT: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+const MD5_SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+const MD5_K: [u32; 64] = [
+	0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+	0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+	0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+	0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+	0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+	0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+	0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+	0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391
+];
+
+fn md5_compress(state: &mut [u32; 4], block: &[u8; 64]) {
+	let words: Vec<u32> = block.chunks(4)
+		.map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+		.collect();
+
+	let [mut a, mut b, mut c, mut d] = *state;
+
+	for i in 0..64 {
+		let (f, g) = match i / 16 {
+			0 => ((b & c) | (!b & d), i),
+			1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+			2 => (b ^ c ^ d, (3 * i + 5) % 16),
+			_ => (c ^ (b | !d), (7 * i) % 16)
+		};
+
+		let f = f.wrapping_add(a).wrapping_add(MD5_K[i]).wrapping_add(words[g]);
+		a = d;
+		d = c;
+		c = b;
+		b = b.wrapping_add(f.rotate_left(MD5_SHIFTS[(i / 16) * 4 + i % 4]));
+	}
+
+	for (value, new_value) in state.iter_mut().zip([a, b, c, d]) {
+		*value = value.wrapping_add(new_value);
+	}
+}
+
+const SHA1_INIT: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+fn sha1_compress(state: &mut [u32; 5], block: &[u8; 64]) {
+	let mut words = [0u32; 80];
+	for (i, word) in block.chunks(4).enumerate() {
+		words[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+	}
+	for i in 16..80 {
+		words[i] = (words[i-3] ^ words[i-8] ^ words[i-14] ^ words[i-16]).rotate_left(1);
+	}
+
+	let [mut a, mut b, mut c, mut d, mut e] = *state;
+
+	for (i, word) in words.iter().enumerate() {
+		let (f, k) = match i / 20 {
+			0 => ((b & c) | (!b & d), 0x5a827999),
+			1 => (b ^ c ^ d, 0x6ed9eba1),
+			2 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+			_ => (b ^ c ^ d, 0xca62c1d6)
+		};
+
+		let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*word);
+		e = d;
+		d = c;
+		c = b.rotate_left(30);
+		b = a;
+		a = temp;
+	}
+
+	for (value, new_value) in state.iter_mut().zip([a, b, c, d, e]) {
+		*value = value.wrapping_add(new_value);
+	}
+}
+
+const SHA256_INIT: [u32; 8] = [
+	0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19
+];
+
+const SHA256_K: [u32; 64] = [
+	0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+	0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+	0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+	0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+	0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+	0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+	0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+	0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2
+];
+
+fn sha256_compress(state: &mut [u32; 8], block: &[u8; 64]) {
+	let mut words = [0u32; 64];
+	for (i, word) in block.chunks(4).enumerate() {
+		words[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+	}
+	for i in 16..64 {
+		let s0 = words[i-15].rotate_right(7) ^ words[i-15].rotate_right(18) ^ (words[i-15] >> 3);
+		let s1 = words[i-2].rotate_right(17) ^ words[i-2].rotate_right(19) ^ (words[i-2] >> 10);
+		words[i] = words[i-16].wrapping_add(s0).wrapping_add(words[i-7]).wrapping_add(s1);
+	}
+
+	let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+
+	for (word, k) in words.iter().zip(SHA256_K) {
+		let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+		let choice = (e & f) ^ (!e & g);
+		let temp1 = h.wrapping_add(s1).wrapping_add(choice).wrapping_add(k).wrapping_add(*word);
+		let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+		let majority = (a & b) ^ (a & c) ^ (b & c);
+		let temp2 = s0.wrapping_add(majority);
+
+		h = g;
+		g = f;
+		f = e;
+		e = d.wrapping_add(temp1);
+		d = c;
+		c = b;
+		b = a;
+		a = temp1.wrapping_add(temp2);
+	}
+
+	for (value, new_value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+		*value = value.wrapping_add(new_value);
+	}
+}
+
+const SHA512_INIT: [u64; 8] = [
+	0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+	0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179
+];
+
+const SHA512_K: [u64; 80] = [
+	0x428a2f98d728ae22, 0x7137449123ef65cd, 0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc,
+	0x3956c25bf348b538, 0x59f111f1b605d019, 0x923f82a4af194f9b, 0xab1c5ed5da6d8118,
+	0xd807aa98a3030242, 0x12835b0145706fbe, 0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2,
+	0x72be5d74f27b896f, 0x80deb1fe3b1696b1, 0x9bdc06a725c71235, 0xc19bf174cf692694,
+	0xe49b69c19ef14ad2, 0xefbe4786384f25e3, 0x0fc19dc68b8cd5b5, 0x240ca1cc77ac9c65,
+	0x2de92c6f592b0275, 0x4a7484aa6ea6e483, 0x5cb0a9dcbd41fbd4, 0x76f988da831153b5,
+	0x983e5152ee66dfab, 0xa831c66d2db43210, 0xb00327c898fb213f, 0xbf597fc7beef0ee4,
+	0xc6e00bf33da88fc2, 0xd5a79147930aa725, 0x06ca6351e003826f, 0x142929670a0e6e70,
+	0x27b70a8546d22ffc, 0x2e1b21385c26c926, 0x4d2c6dfc5ac42aed, 0x53380d139d95b3df,
+	0x650a73548baf63de, 0x766a0abb3c77b2a8, 0x81c2c92e47edaee6, 0x92722c851482353b,
+	0xa2bfe8a14cf10364, 0xa81a664bbc423001, 0xc24b8b70d0f89791, 0xc76c51a30654be30,
+	0xd192e819d6ef5218, 0xd69906245565a910, 0xf40e35855771202a, 0x106aa07032bbd1b8,
+	0x19a4c116b8d2d0c8, 0x1e376c085141ab53, 0x2748774cdf8eeb99, 0x34b0bcb5e19b48a8,
+	0x391c0cb3c5c95a63, 0x4ed8aa4ae3418acb, 0x5b9cca4f7763e373, 0x682e6ff3d6b2b8a3,
+	0x748f82ee5defb2fc, 0x78a5636f43172f60, 0x84c87814a1f0ab72, 0x8cc702081a6439ec,
+	0x90befffa23631e28, 0xa4506cebde82bde9, 0xbef9a3f7b2c67915, 0xc67178f2e372532b,
+	0xca273eceea26619c, 0xd186b8c721c0c207, 0xeada7dd6cde0eb1e, 0xf57d4f7fee6ed178,
+	0x06f067aa72176fba, 0x0a637dc5a2c898a6, 0x113f9804bef90dae, 0x1b710b35131c471b,
+	0x28db77f523047d84, 0x32caab7b40c72493, 0x3c9ebe0a15c9bebc, 0x431d67c49c100d4c,
+	0x4cc5d4becb3e42b6, 0x597f299cfc657e2a, 0x5fcb6fab3ad6faec, 0x6c44198c4a475817
+];
+
+fn sha512_compress(state: &mut [u64; 8], block: &[u8; 128]) {
+	let mut words = [0u64; 80];
+	for (i, word) in block.chunks(8).enumerate() {
+		words[i] = u64::from_be_bytes(word.try_into().unwrap());
+	}
+	for i in 16..80 {
+		let s0 = words[i-15].rotate_right(1) ^ words[i-15].rotate_right(8) ^ (words[i-15] >> 7);
+		let s1 = words[i-2].rotate_right(19) ^ words[i-2].rotate_right(61) ^ (words[i-2] >> 6);
+		words[i] = words[i-16].wrapping_add(s0).wrapping_add(words[i-7]).wrapping_add(s1);
+	}
+
+	let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+
+	for (word, k) in words.iter().zip(SHA512_K) {
+		let s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+		let choice = (e & f) ^ (!e & g);
+		let temp1 = h.wrapping_add(s1).wrapping_add(choice).wrapping_add(k).wrapping_add(*word);
+		let s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+		let majority = (a & b) ^ (a & c) ^ (b & c);
+		let temp2 = s0.wrapping_add(majority);
+
+		h = g;
+		g = f;
+		f = e;
+		e = d.wrapping_add(temp1);
+		d = c;
+		c = b;
+		b = a;
+		a = temp1.wrapping_add(temp2);
+	}
+
+	for (value, new_value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+		*value = value.wrapping_add(new_value);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::expression::NoContext;
+
+	/// Hex digest of `data`, given to the hasher in chunks of `chunk_size` bytes
+	fn hex_digest(algorithm: Algorithm, data: &[u8], chunk_size: usize) -> String {
+		let mut hasher = algorithm.hasher();
+
+		for chunk in data.chunks(chunk_size.max(1)) {
+			hasher.update(chunk);
+		}
+
+		hasher.finalize()
+			.iter()
+			.map(|byte| format!("{:02x}", byte))
+			.collect()
+	}
+
+	#[test]
+	fn check_values() {
+		// standard "check" of each algorithm, computed over "123456789"
+		let expected = [
+			(Algorithm::Crc16, "bb3d"),
+			(Algorithm::Crc16Ccitt, "29b1"),
+			(Algorithm::Crc16Xmodem, "31c3"),
+			(Algorithm::Crc32, "cbf43926"),
+			(Algorithm::Adler32, "091e01de"),
+		];
+
+		for (algorithm, digest) in expected {
+			assert_eq!(hex_digest(algorithm, b"123456789", 9), digest, "{}", algorithm.name());
+		}
+	}
+
+	#[test]
+	fn hashes_of_short_inputs() {
+		let expected = [
+			(Algorithm::Md5, &b""[..], "d41d8cd98f00b204e9800998ecf8427e"),
+			(Algorithm::Md5, b"abc", "900150983cd24fb0d6963f7d28e17f72"),
+			(Algorithm::Sha1, b"abc", "a9993e364706816aba3e25717850c26c9cd0d89d"),
+			(Algorithm::Sha256, b"abc", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+			(Algorithm::Sha512, b"abc", "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+				2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"),
+		];
+
+		for (algorithm, data, digest) in expected {
+			assert_eq!(hex_digest(algorithm, data, 64), digest, "{}", algorithm.name());
+		}
+	}
+
+	#[test]
+	fn hashes_of_several_blocks() {
+		let message = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+		let long_message = b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmno\
+			ijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu";
+
+		let expected = [
+			(Algorithm::Md5, &message[..], "8215ef0796a20bcaaae116d3876c664a"),
+			(Algorithm::Sha1, message, "84983e441c3bd26ebaae4aa1f95129e5e54670f1"),
+			(Algorithm::Sha256, message, "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"),
+			(Algorithm::Sha512, long_message, "8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018\
+				501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909"),
+		];
+
+		for (algorithm, data, digest) in expected {
+			assert_eq!(hex_digest(algorithm, data, 1000), digest, "{}", algorithm.name());
+		}
+	}
+
+	#[test]
+	fn padding_at_the_end_of_a_block() {
+		let expected = [
+			(Algorithm::Md5, 55, "ef1772b6dff9a122358552954ad0df65"),
+			(Algorithm::Md5, 56, "3b0c8ac703f828b04c6c197006d17218"),
+			(Algorithm::Md5, 64, "014842d480b571495a4a0363793f7367"),
+			(Algorithm::Sha1, 55, "c1c8bbdc22796e28c0e15163d20899b65621d65a"),
+			(Algorithm::Sha1, 56, "c2db330f6083854c99d4b5bfb6e8f29f201be699"),
+			(Algorithm::Sha1, 64, "0098ba824b5c16427bd7a1122a5a442a25ec644d"),
+			(Algorithm::Sha256, 55, "9f4390f8d30c2dd92ec9f095b65e2b9ae9b0a925a5258e241c9f1e910f734318"),
+			(Algorithm::Sha256, 56, "b35439a4ac6f0948b6d6f9e3c6af0f5f590ce20f1bde7090ef7970686ec6738a"),
+			(Algorithm::Sha256, 64, "ffe054fe7ae0cb6dc65c3af9b61d5209f439851db43d0ba5997337df154668eb"),
+			(Algorithm::Sha512, 111, "fa9121c7b32b9e01733d034cfc78cbf67f926c7ed83e82200ef8681819692176\
+				0b4beff48404df811b953828274461673c68d04e297b0eb7b2b4d60fc6b566a2"),
+			(Algorithm::Sha512, 112, "c01d080efd492776a1c43bd23dd99d0a2e626d481e16782e75d54c2503b5dc32\
+				bd05f0f1ba33e568b88fd2d970929b719ecbb152f58f130a407c8830604b70ca"),
+			(Algorithm::Sha512, 128, "b73d1929aa615934e61a871596b3f3b33359f42b8175602e89f7e06e5f658a24\
+				3667807ed300314b95cacdd579f3e33abdfbe351909519a846d465c59582f321"),
+		];
+
+		for (algorithm, len, digest) in expected {
+			assert_eq!(hex_digest(algorithm, &vec![b'a'; len], 64), digest, "{} of {} bytes", algorithm.name(), len);
+		}
+	}
+
+	#[test]
+	fn fixcrc_endianness() {
+		let preset = ChecksumPreset::parse("crc32 0x10..0x20 0x8", &mut NoContext).unwrap();
+		assert_eq!(preset.encode(&[1, 2, 3, 4]), [4, 3, 2, 1]);
+		assert_eq!(preset.to_string(), "crc32 0x10..0x20 0x8 le");
+
+		let preset = ChecksumPreset::parse("crc16 0x10..0x20 0x8 be", &mut NoContext).unwrap();
+		assert_eq!(preset.encode(&[1, 2]), [1, 2]);
+		assert_eq!(ChecksumPreset::parse(&preset.to_string(), &mut NoContext), Ok(preset));
+
+		let preset = ChecksumPreset::parse("md5 0x10..0x20 0x100", &mut NoContext).unwrap();
+		assert_eq!(preset.encode(&[1, 2, 3, 4]), [1, 2, 3, 4]);
+		assert_eq!(preset.to_string(), "md5 0x10..0x20 0x100");
+
+		assert!(ChecksumPreset::parse("sha256 0x10..0x20 0x100 le", &mut NoContext).is_err());
+		assert!(ChecksumPreset::parse("sha1 0x10..0x20 0x100 be", &mut NoContext).is_err());
+		assert!(ChecksumPreset::parse("crc32 0x10..0x20 0x8 me", &mut NoContext).is_err());
+	}
+
+	#[test]
+	fn chunks_dont_change_the_digest() {
+		let data: Vec<u8> = (0..100_000u32).map(|i| (i * 7 % 256) as u8).collect();
+
+		let expected = [
+			(Algorithm::Crc16, "dcea"),
+			(Algorithm::Crc32, "0eaf0153"),
+			(Algorithm::Adler32, "6a10942f"),
+			(Algorithm::Md5, "8cb7f568752be648376cd32a342006af"),
+			(Algorithm::Sha1, "db6498cdec44effeaa7284401972c6af8645d842"),
+			(Algorithm::Sha256, "931030b89f42c06dcdda12a43dfcd601d745d11bbb5fcd1a00fea442e8405157"),
+			(Algorithm::Sha512, "7d50c71109cca3d623c5fc4d3730f54c72e1bba09015e00ceeb0af9d361b04c2\
+				eb67eea8fd2b0105295bca3ff9d038f2ed4fc64991b9aaea70225b166a7ead15"),
+		];
+
+		for (algorithm, digest) in expected {
+			for chunk_size in [1, 63, 64, 65, 127, 0x10000] {
+				assert_eq!(hex_digest(algorithm, &data, chunk_size), digest, "{} in chunks of {}", algorithm.name(), chunk_size);
+			}
+		}
+	}
+}
@@ -0,0 +1,528 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::expression::{self, Endianness, NoContext};
+use crate::transform::parse_hex_bytes;
+
+/// Interpretations of the bytes at the cursor displayed by the inspector
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+	Unsigned(usize),	// size in bytes
+	Signed(usize),
+	Float(usize),		// f16, f32 or f64
+	UnixTime(usize),	// time_t of 32 or 64 bits, in seconds since 1970
+	Uleb128,
+	Sleb128,
+	Varint,			// zigzag encoded, like the signed integers of protobuf
+	Filetime,		// Windows FILETIME, in 100 ns since 1601
+	DosDateTime,	// time then date, like in the zip headers
+	Guid,			// the first 3 fields are little endian, like on Windows
+	Ipv4,
+	Ipv6,
+	Mac
+}
+
+/// A line of the inspector: an interpretation and its endianness, i.e `u32le`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Field {
+	pub kind: Kind,
+	pub endianness: Endianness	// only used by the integers, the floats and the unix times
+}
+
+/// Seconds between the 1601-01-01 of the FILETIME and the 1970-01-01 of the unix time
+const FILETIME_UNIX_EPOCH: i64 = 11_644_473_600;
+
+/// Fields displayed by the inspector, in this order
+pub fn fields() -> Vec<Field> {
+	let mut fields = vec![
+		Field { kind: Kind::Unsigned(1), endianness: Endianness::Little },
+		Field { kind: Kind::Signed(1), endianness: Endianness::Little }
+	];
+
+	let sized_kinds = [
+		Kind::Unsigned(2), Kind::Signed(2), Kind::Unsigned(4), Kind::Signed(4), Kind::Unsigned(8), Kind::Signed(8),
+		Kind::Float(2), Kind::Float(4), Kind::Float(8), Kind::UnixTime(4), Kind::UnixTime(8)
+	];
+
+	for kind in sized_kinds {
+		for endianness in [Endianness::Little, Endianness::Big] {
+			fields.push(Field { kind, endianness });
+		}
+	}
+
+	let other_kinds = [
+		Kind::Uleb128, Kind::Sleb128, Kind::Varint, Kind::Filetime, Kind::DosDateTime,
+		Kind::Guid, Kind::Ipv4, Kind::Ipv6, Kind::Mac
+	];
+	fields.extend(other_kinds.map(|kind| Field { kind, endianness: Endianness::Little }));
+
+	fields
+}
+
+impl Field {
+	/// Field named `name`, as displayed by the inspector
+	pub fn parse(name: &str) -> Option<Field> {
+		let name = name.trim().to_lowercase();
+		fields().into_iter().find(|field| field.name() == name)
+	}
+
+	/// Name displayed by the inspector, i.e `u32le`, `f64be` or `guid`
+	pub fn name(&self) -> String {
+		let endianness = match self.endianness {
+			Endianness::Little => "le",
+			Endianness::Big => "be"
+		};
+
+		match self.kind {
+			Kind::Unsigned(1) => "u8".to_string(),
+			Kind::Signed(1) => "i8".to_string(),
+			Kind::Unsigned(size) => format!("u{}{}", size * 8, endianness),
+			Kind::Signed(size) => format!("i{}{}", size * 8, endianness),
+			Kind::Float(size) => format!("f{}{}", size * 8, endianness),
+			Kind::UnixTime(size) => format!("time{}{}", size * 8, endianness),
+			Kind::Uleb128 => "uleb128".to_string(),
+			Kind::Sleb128 => "leb128".to_string(),
+			Kind::Varint => "varint".to_string(),
+			Kind::Filetime => "filetime".to_string(),
+			Kind::DosDateTime => "dostime".to_string(),
+			Kind::Guid => "guid".to_string(),
+			Kind::Ipv4 => "ipv4".to_string(),
+			Kind::Ipv6 => "ipv6".to_string(),
+			Kind::Mac => "mac".to_string()
+		}
+	}
+
+	/// Number of bytes read, `None` for the variable length integers
+	pub fn size(&self) -> Option<usize> {
+		match self.kind {
+			Kind::Unsigned(size) | Kind::Signed(size) | Kind::Float(size) | Kind::UnixTime(size) => Some(size),
+			Kind::Uleb128 | Kind::Sleb128 | Kind::Varint => None,
+			Kind::Filetime => Some(8),
+			Kind::DosDateTime | Kind::Ipv4 => Some(4),
+			Kind::Guid | Kind::Ipv6 => Some(16),
+			Kind::Mac => Some(6)
+		}
+	}
+
+	/// Value of the bytes at the start of `bytes`. `None` if there are not enough bytes,
+	/// or if they are not valid for this field
+	pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+		let bytes = match self.size() {
+			Some(size) => bytes.get(..size)?,
+			None => bytes
+		};
+
+		match self.kind {
+			Kind::Unsigned(_) => Some(format!("{}", read_unsigned(bytes, self.endianness))),
+			Kind::Signed(_) => Some(format!("{}", read_signed(bytes, self.endianness))),
+			Kind::Float(2) => Some(format!("{:?}", f16_to_f32(read_unsigned(bytes, self.endianness) as u16))),
+			Kind::Float(4) => Some(format!("{:?}", f32::from_bits(read_unsigned(bytes, self.endianness) as u32))),
+			Kind::Float(_) => Some(format!("{:?}", f64::from_bits(read_unsigned(bytes, self.endianness)))),
+			Kind::UnixTime(_) => format_unix_time(read_signed(bytes, self.endianness)),
+			Kind::Uleb128 => read_uleb128(bytes).map(|(value, _)| format!("{}", value)),
+			Kind::Sleb128 => read_sleb128(bytes).map(|value| format!("{}", value)),
+			Kind::Varint => read_uleb128(bytes).map(|(value, _)| format!("{}", zigzag_decode(value))),
+			Kind::Filetime => {
+				let intervals = read_unsigned(bytes, Endianness::Little);
+				let seconds = i64::try_from(intervals / 10_000_000).ok()? - FILETIME_UNIX_EPOCH;
+				format_unix_time(seconds)
+			},
+			Kind::DosDateTime => {
+				let time = read_unsigned(&bytes[..2], Endianness::Little);
+				let date = read_unsigned(&bytes[2..], Endianness::Little);
+
+				let (year, month, day) = (1980 + (date >> 9), (date >> 5) & 0xf, date & 0x1f);
+				let (hours, minutes, seconds) = (time >> 11, (time >> 5) & 0x3f, (time & 0x1f) * 2);
+
+				if month == 0 || month > 12 || day == 0 || hours > 23 || minutes > 59 || seconds > 59 {
+					return None;
+				}
+				Some(format!("{}-{:02}-{:02} {:02}:{:02}:{:02}", year, month, day, hours, minutes, seconds))
+			},
+			Kind::Guid => Some(format!(
+				"{:08x}-{:04x}-{:04x}-{}-{}",
+				read_unsigned(&bytes[..4], Endianness::Little),
+				read_unsigned(&bytes[4..6], Endianness::Little),
+				read_unsigned(&bytes[6..8], Endianness::Little),
+				hex_string(&bytes[8..10]),
+				hex_string(&bytes[10..])
+			)),
+			Kind::Ipv4 => Some(Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?).to_string()),
+			Kind::Ipv6 => Some(Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?).to_string()),
+			Kind::Mac => Some(bytes.iter().map(|byte| format!("{:02x}", byte)).collect::<Vec<String>>().join(":"))
+		}
+	}
+
+	/// Bytes of the value `input`: a number or an expression for the integers, a float,
+	/// a date (`2024-03-01 12:00:00`) or a number of seconds for the times, or an address
+	pub fn encode(&self, input: &str) -> Result<Vec<u8>, String> {
+		let input = input.trim();
+		let too_big = || format!("{} doesn't fit in {}", input, self.name());
+
+		match self.kind {
+			Kind::Unsigned(size) => {
+				let max = u64::MAX >> (64 - 8 * size);
+				let value = u64::try_from(parse_integer(input)?).ok()
+					.filter(|value| *value <= max)
+					.ok_or_else(too_big)?;
+
+				Ok(write_unsigned(value, size, self.endianness))
+			},
+			Kind::Signed(size) => {
+				let bits = 8 * size as u32;
+				let value = parse_integer(input)?;
+
+				if value < -(1 << (bits - 1)) || value >= 1 << (bits - 1) {
+					return Err(too_big());
+				}
+				Ok(write_unsigned(value as u64, size, self.endianness))
+			},
+			Kind::Float(size) => {
+				let value: f64 = input.parse().map_err(|_| format!("Invalid float {}", input))?;
+
+				// a finite value is not written as an infinity
+				let bits = match size {
+					2 | 4 if value.is_finite() && (value as f32).is_infinite() => return Err(too_big()),
+					2 => u64::from(f32_to_f16(value as f32).ok_or_else(too_big)?),
+					4 => u64::from((value as f32).to_bits()),
+					_ => value.to_bits()
+				};
+				Ok(write_unsigned(bits, size, self.endianness))
+			},
+			Kind::UnixTime(size) => {
+				let seconds = parse_time(input)?;
+
+				if size == 4 && i32::try_from(seconds).is_err() {
+					return Err(too_big());
+				}
+				Ok(write_unsigned(seconds as u64, size, self.endianness))
+			},
+			Kind::Uleb128 => {
+				let value = u64::try_from(parse_integer(input)?).map_err(|_| too_big())?;
+				Ok(write_uleb128(value))
+			},
+			Kind::Sleb128 => {
+				let value = i64::try_from(parse_integer(input)?).map_err(|_| too_big())?;
+				Ok(write_sleb128(value))
+			},
+			Kind::Varint => {
+				let value = i64::try_from(parse_integer(input)?).map_err(|_| too_big())?;
+				Ok(write_uleb128(((value << 1) ^ (value >> 63)) as u64))
+			},
+			Kind::Filetime => {
+				let intervals = parse_time(input)?
+					.checked_add(FILETIME_UNIX_EPOCH)
+					.and_then(|seconds| u64::try_from(seconds).ok())
+					.and_then(|seconds| seconds.checked_mul(10_000_000))
+					.ok_or_else(too_big)?;
+
+				Ok(intervals.to_le_bytes().to_vec())
+			},
+			Kind::DosDateTime => {
+				let (year, month, day, hours, minutes, seconds) = civil_time(parse_time(input)?).ok_or_else(too_big)?;
+
+				if !(1980..=2107).contains(&year) {
+					return Err(too_big());
+				}
+
+				let date = ((year - 1980) << 9) | (month << 5) | day;
+				let time = (hours << 11) | (minutes << 5) | (seconds / 2);
+
+				let mut bytes = write_unsigned(time as u64, 2, Endianness::Little);
+				bytes.extend(write_unsigned(date as u64, 2, Endianness::Little));
+				Ok(bytes)
+			},
+			Kind::Guid => {
+				let hex: String = input.trim_matches(|c| c == '{' || c == '}').split('-').collect();
+				let mut bytes = parse_hex_bytes(&hex)
+					.filter(|bytes| bytes.len() == 16)
+					.ok_or(format!("Invalid GUID {}", input))?;
+
+				// the first 3 fields are little endian
+				bytes[..4].reverse();
+				bytes[4..6].reverse();
+				bytes[6..8].reverse();
+				Ok(bytes)
+			},
+			Kind::Ipv4 => input.parse::<Ipv4Addr>()
+				.map(|address| address.octets().to_vec())
+				.map_err(|_| format!("Invalid IPv4 address {}", input)),
+			Kind::Ipv6 => input.parse::<Ipv6Addr>()
+				.map(|address| address.octets().to_vec())
+				.map_err(|_| format!("Invalid IPv6 address {}", input)),
+			Kind::Mac => input.split([':', '-'])
+				.map(|byte| u8::from_str_radix(byte, 16).ok())
+				.collect::<Option<Vec<u8>>>()
+				.filter(|bytes| bytes.len() == 6)
+				.ok_or(format!("Invalid MAC address {}", input))
+		}
+	}
+}
+
+/// Integer given as a number or an expression, i.e `-12`, `0xff` or `1<<20`
+fn parse_integer(input: &str) -> Result<i128, String> {
+	expression::evaluate_signed(input, &mut NoContext)
+}
+
+/// Unix time given as a date, i.e `2024-03-01 12:00:00`, or as a number of seconds
+fn parse_time(input: &str) -> Result<i64, String> {
+	let invalid_date = || format!("Invalid date {}, use YYYY-MM-DD [HH:MM:SS] or a number of seconds", input);
+
+	if let Some(seconds) = parse_date(input) {
+		return Ok(seconds);
+	}
+
+	// a date that doesn't exist, like `2023-02-30`, is not a subtraction
+	let date = input.split([' ', 'T']).next().unwrap_or(input);
+	if input.contains(':') || (!date.starts_with('-') && date.split('-').count() == 3) {
+		return Err(invalid_date());
+	}
+
+	let seconds = parse_integer(input).map_err(|_| invalid_date())?;
+	i64::try_from(seconds).map_err(|_| invalid_date())
+}
+
+/// Unix time of a date `YYYY-MM-DD`, followed by an optional time `HH:MM[:SS]` and `UTC`
+fn parse_date(input: &str) -> Option<i64> {
+	let input = input.trim_end_matches("UTC").trim_end_matches('Z').trim();
+	let (date, time) = input.split_once([' ', 'T']).unwrap_or((input, "00:00"));
+
+	let date: Vec<i64> = date.split('-').map(|part| part.parse().ok()).collect::<Option<_>>()?;
+	let time: Vec<i64> = time.trim().split(':').map(|part| part.parse().ok()).collect::<Option<_>>()?;
+
+	let [year, month, day] = date[..] else { return None };
+	let (hours, minutes, seconds) = match time[..] {
+		[hours, minutes] => (hours, minutes, 0),
+		[hours, minutes, seconds] => (hours, minutes, seconds),
+		_ => return None
+	};
+
+	if !(0..24).contains(&hours) || !(0..60).contains(&minutes) || !(0..60).contains(&seconds) {
+		return None;
+	}
+
+	let days = days_from_civil(year, month, day)?;
+
+	// reject the days that don't exist, like the 2023-02-30
+	let seconds = days.checked_mul(86400)?.checked_add(hours * 3600 + minutes * 60 + seconds)?;
+	match civil_time(seconds) {
+		Some((y, m, d, _, _, _)) if (y, m, d) == (year, month, day) => Some(seconds),
+		_ => None
+	}
+}
+
+/// `size` bytes of `value`
+fn write_unsigned(value: u64, size: usize, endianness: Endianness) -> Vec<u8> {
+	match endianness {
+		Endianness::Little => value.to_le_bytes()[..size].to_vec(),
+		Endianness::Big => value.to_be_bytes()[8 - size..].to_vec()
+	}
+}
+
+fn write_uleb128(mut value: u64) -> Vec<u8> {
+	let mut bytes = vec![];
+
+	loop {
+		let byte = (value & 0x7f) as u8;
+		value >>= 7;
+
+		if value == 0 {
+			bytes.push(byte);
+			return bytes;
+		}
+		bytes.push(byte | 0x80);
+	}
+}
+
+fn write_sleb128(mut value: i64) -> Vec<u8> {
+	let mut bytes = vec![];
+
+	loop {
+		let byte = (value & 0x7f) as u8;
+		value >>= 7;
+
+		// stop when the sign bit of the byte matches the remaining bits
+		if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+			bytes.push(byte);
+			return bytes;
+		}
+		bytes.push(byte | 0x80);
+	}
+}
+
+/// Unsigned integer stored in `bytes`, at most 8 bytes
+fn read_unsigned(bytes: &[u8], endianness: Endianness) -> u64 {
+	let fold = |value: u64, byte: &u8| (value << 8) | u64::from(*byte);
+
+	match endianness {
+		Endianness::Little => bytes.iter().rev().fold(0, fold),
+		Endianness::Big => bytes.iter().fold(0, fold)
+	}
+}
+
+/// Signed integer stored in `bytes`, at most 8 bytes
+fn read_signed(bytes: &[u8], endianness: Endianness) -> i64 {
+	let shift = 64 - 8 * bytes.len() as u32;
+	((read_unsigned(bytes, endianness) << shift) as i64) >> shift
+}
+
+/// Unsigned LEB128 at the start of `bytes`, with the number of bytes it uses
+fn read_uleb128(bytes: &[u8]) -> Option<(u64, usize)> {
+	let mut value: u64 = 0;
+
+	for (i, byte) in bytes.iter().enumerate().take(10) {
+		value |= u64::from(byte & 0x7f).checked_shl(7 * i as u32)?;
+
+		if byte & 0x80 == 0 {
+			return Some((value, i + 1));
+		}
+	}
+	None
+}
+
+/// Signed LEB128 at the start of `bytes`
+fn read_sleb128(bytes: &[u8]) -> Option<i64> {
+	let (value, len) = read_uleb128(bytes)?;
+
+	// extend the sign bit of the last byte
+	let bits = 7 * len as u32;
+	match bits < 64 && value & (1 << (bits - 1)) != 0 {
+		true => Some((value | (u64::MAX << bits)) as i64),
+		false => Some(value as i64)
+	}
+}
+
+fn zigzag_decode(value: u64) -> i64 {
+	((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+/// Convert an half precision float to a f32
+fn f16_to_f32(bits: u16) -> f32 {
+	let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+	let exponent = i32::from((bits >> 10) & 0x1f);
+	let mantissa = f32::from(bits & 0x3ff);
+
+	match exponent {
+		0 => sign * mantissa * 2f32.powi(-24),	// subnormal
+		0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+		0x1f => f32::NAN,
+		_ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15)
+	}
+}
+
+/// Convert a f32 to an half precision float, `None` if it is too big
+fn f32_to_f16(value: f32) -> Option<u16> {
+	let sign: u16 = if value.is_sign_negative() { 0x8000 } else { 0 };
+	let abs = value.abs();
+
+	if value.is_nan() {
+		return Some(0x7e00);
+	}
+	if value.is_infinite() {
+		return Some(sign | 0x7c00);
+	}
+
+	// subnormal, rounds to the smallest normal number if needed
+	if abs < 2f32.powi(-14) {
+		return Some(sign | (abs / 2f32.powi(-24)).round() as u16);
+	}
+
+	let mut exponent = ((abs.to_bits() >> 23) & 0xff) as i32 - 127;
+	let mut mantissa = ((abs / 2f32.powi(exponent) - 1.0) * 1024.0).round() as u16;
+
+	if mantissa == 1024 {
+		exponent += 1;
+		mantissa = 0;
+	}
+
+	match exponent {
+		exponent if exponent > 15 => None,
+		exponent => Some(sign | (((exponent + 15) as u16) << 10) | mantissa)
+	}
+}
+
+/// Date of a unix time, i.e `2024-03-01 12:00:00 UTC`
+fn format_unix_time(seconds: i64) -> Option<String> {
+	let (year, month, day, hours, minutes, seconds) = civil_time(seconds)?;
+
+	Some(format!(
+		"{}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+		year, month, day, hours, minutes, seconds
+	))
+}
+
+/// Number of days since 1970-01-01 of a date (Howard Hinnant's algorithm), `None` if it overflows
+fn days_from_civil(year: i64, month: i64, day: i64) -> Option<i64> {
+	if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+		return None;
+	}
+
+	let year = if month <= 2 { year.checked_sub(1)? } else { year };
+	let era = year.div_euclid(400);
+	let year_of_era = year.rem_euclid(400);
+	let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+	let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+	era.checked_mul(146_097)?.checked_add(day_of_era - 719_468)
+}
+
+/// Date and time of a unix time: (year, month, day, hours, minutes, seconds)
+fn civil_time(seconds: i64) -> Option<(i64, i64, i64, i64, i64, i64)> {
+	let days = seconds.div_euclid(86400);
+	let time = seconds.rem_euclid(86400);
+
+	// civil date from the number of days since 1970-01-01 (Howard Hinnant's algorithm)
+	let z = days.checked_add(719_468)?;
+	let era = z.div_euclid(146_097);
+	let day_of_era = z.rem_euclid(146_097);
+	let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let month_index = (5 * day_of_year + 2) / 153;
+
+	let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+	let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+	let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+	Some((year, month, day, time / 3600, time % 3600 / 60, time % 60))
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+	bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn encode(field: &str, input: &str) -> Result<Vec<u8>, String> {
+		Field::parse(field).unwrap().encode(input)
+	}
+
+	#[test]
+	fn dates() {
+		assert_eq!(parse_date("1970-01-01"), Some(0));
+		assert_eq!(parse_date("2024-03-01 12:00:00 UTC"), Some(1_709_294_400));
+		assert_eq!(parse_date("2024-02-29T23:59"), Some(1_709_251_140));
+		assert_eq!(parse_date("2023-02-29"), None);
+		assert_eq!(parse_date("2023-13-01"), None);
+
+		// the years that overflow are rejected
+		assert_eq!(parse_date("9223372036854775807-01-01"), None);
+		assert_eq!(parse_date("-9223372036854775808-01-01"), None);
+		assert_eq!(parse_date("100000000000000-01-01"), None);
+		assert!(encode("time64le", "100000000000000-01-01").is_err());
+	}
+
+	#[test]
+	fn floats_too_big() {
+		assert_eq!(encode("f16le", "1.5"), Ok(vec![0x00, 0x3e]));
+		assert_eq!(encode("f16be", "65504"), Ok(vec![0x7b, 0xff]));
+		assert_eq!(encode("f16le", "inf"), Ok(vec![0x00, 0x7c]));
+		assert!(encode("f16le", "65536").is_err());
+		assert!(encode("f16le", "1e300").is_err());
+		assert!(encode("f16le", "-1e300").is_err());
+
+		assert_eq!(encode("f32be", "-inf"), Ok(vec![0xff, 0x80, 0x00, 0x00]));
+		assert!(encode("f32le", "1e300").is_err());
+		assert_eq!(encode("f64be", "1e300").map(|bytes| bytes.len()), Ok(8));
+	}
+}
@@ -2,11 +2,12 @@ use std::{error::Error, io, process::exit};
 use colored::Colorize;
 use clap::Parser;
 
-use app::{Braille, CommandBar, CurrentEditor};
+use encoding::Encoding;
+use app::{Braille, Columns, CommandBar, CurrentEditor, Modification, View};
 use crossterm::{
 	cursor, event::{
-		self, Event, KeyCode, KeyEvent, KeyModifiers
-	}, terminal::{
+		self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind
+	}, execute, terminal::{
 		disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen
 	}
 };
@@ -18,6 +19,14 @@ use ratatui::{
 mod ui;
 mod app;
 mod search;
+mod transform;
+mod hash;
+mod state;
+mod stats;
+mod marks;
+mod expression;
+mod encoding;
+mod inspector;
 
 use crate::{
     app::{App, Mode},
@@ -42,6 +51,18 @@ struct Args {
 	// Seek to defined byte
 	#[arg(short, long, help = "Go to this address. I.e `-s 0xc0ffee`")]
 	seek: Option<String>,
+
+	// Number of bytes per line
+	#[arg(short, long, help = "Number of bytes per line, or `auto` to fit the terminal. I.e `-c 32`")]
+	columns: Option<String>,
+
+	// Base address added to the displayed addresses
+	#[arg(long, help = "Display the addresses from this base address, i.e for a memory dump. I.e `--base 0x08000000`")]
+	base: Option<String>,
+
+	// Encoding of the text pane
+	#[arg(short, long, help = "Encoding of the text pane: ascii, latin1, cp437, cp037, utf8, utf16le or utf16be")]
+	encoding: Option<String>,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -64,7 +85,58 @@ fn main() -> Result<(), Box<dyn Error>> {
 		None => None,
 	};
 
-	let app = App::new(args.file, braille_mode, seek)?;
+	// Parse --base parameter
+	let base_address = match args.base {
+		Some(base) => match parse_seek(&base) {
+			Some(base_address) => base_address,
+			None => {
+				eprintln!("{} invalid base address {}", "Error:".red().bold(), base);
+				exit(1);
+			}
+		},
+		None => 0,
+	};
+
+	// Parse --columns parameter
+	let columns = match args.columns {
+		Some(columns) => match Columns::parse(&columns) {
+			Some(columns) => columns,
+			None => {
+				eprintln!("{} invalid number of columns {}, use 1 to {} or auto", "Error:".red().bold(), columns, Columns::MAX);
+				exit(1);
+			}
+		},
+		None => Columns::Fixed(0x10),
+	};
+
+	// Parse --encoding parameter
+	let encoding = match args.encoding {
+		Some(encoding) => match Encoding::parse(&encoding) {
+			Some(encoding) => encoding,
+			None => {
+				eprintln!("{} unknown encoding {}", "Error:".red().bold(), encoding);
+				exit(1);
+			}
+		},
+		None => Encoding::Ascii,
+	};
+
+	// the address to seek to can be a displayed address, so we set the base first
+	let mut app = App::new(args.file, braille_mode, None)?;
+	app.base_address = base_address;
+	app.columns = columns;
+	app.encoding = encoding;
+
+	if let Columns::Fixed(bytes_per_line) = columns {
+		app.set_bytes_per_line(bytes_per_line);
+	}
+
+	if let Some(seek) = seek {
+		match app.file_offset(seek) {
+			Ok(offset) => app.jump_to(offset),
+			Err(message) => app.add_error_message(app::WarningLevel::Warning, message)
+		}
+	}
 
 	/* Some ratatui code to handle panic!() without messing up the terminal */
 
@@ -106,7 +178,7 @@ fn init_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>, io::Error>
 /// Resets the terminal.
 fn reset_terminal() -> Result<(), io::Error> {
     disable_raw_mode()?;
-    crossterm::execute!(io::stdout(), LeaveAlternateScreen, cursor::Show)?;
+    crossterm::execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, cursor::Show)?;
 
     Ok(())
 }
@@ -128,13 +200,36 @@ fn parse_seek(input: &str) -> Option<u64> {
 }
 
 fn handle_keyboard_inputs(mut app: App, terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<(), Box<dyn Error>> {
+	let mut mouse_captured = false;
+
 	loop {
 		app.reset();
 
+		// capture the mouse only while the minimap is displayed,
+		// so the text can still be selected in the terminal otherwise
+		if app.show_minimap != mouse_captured {
+			match app.show_minimap {
+				true => execute!(io::stdout(), EnableMouseCapture)?,
+				false => execute!(io::stdout(), DisableMouseCapture)?
+			}
+			mouse_captured = app.show_minimap;
+		}
+
 		// draw the screen
 		terminal.draw(|f| ui(f, &mut app))?;
 
-		if let Event::Key(key) = event::read()? {
+		let event = event::read()?;
+
+		// click or drag on the minimap to jump in the file
+		if let Event::Mouse(MouseEvent {
+			kind: kind @ (MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Drag(MouseButton::Left)),
+			column, row, ..
+		}) = event {
+			app.click_minimap(column, row, matches!(kind, MouseEventKind::Drag(_)));
+			continue;
+		}
+
+		if let Event::Key(key) = event {
 			
 			// Skip events that are not KeyEventKind::Press
 			if key.kind == event::KeyEventKind::Release {
@@ -145,6 +240,9 @@ fn handle_keyboard_inputs(mut app: App, terminal: &mut Terminal<CrosstermBackend
 			// error message.
 			app.cleanup_error_message();
 
+			// first key of a shortcut of 2 keys, like `ma`
+			let pending_key = app.pending_key.take();
+
 			// shortcuts with Ctrl + key
 			match key {
 
@@ -172,8 +270,8 @@ fn handle_keyboard_inputs(mut app: App, terminal: &mut Terminal<CrosstermBackend
 								app.mode = Mode::Overwrite;
 								// Make sure our cursor isn't after the end of the file
 								// in Overwrite mode
-								if app.cursor >= app.file_size * 2 {
-									app.cursor = (app.file_size * 2).saturating_sub(1);
+								if app.cursor >= app.file_size * app.digits() {
+									app.cursor = (app.file_size * app.digits()).saturating_sub(1);
 								}
 							},
 							Mode::Overwrite => app.mode = Mode::Insert,
@@ -191,45 +289,71 @@ fn handle_keyboard_inputs(mut app: App, terminal: &mut Terminal<CrosstermBackend
 				KeyEvent {
 					modifiers: KeyModifiers::CONTROL,
 					code: KeyCode::Right,  ..
-				} => {app.change_cursor(0x7)},
+				} => {app.change_cursor(4 * app.digits() as i64 - 1)},
 
 				KeyEvent {
 					modifiers: KeyModifiers::CONTROL,
 					code: KeyCode::Left,  ..
-				} => {app.change_cursor(-0x7)},
+				} => {app.change_cursor(-(4 * app.digits() as i64 - 1))},
 
 				// Ctrl + Up / Down: jump by 4 lines
 				KeyEvent {
 					modifiers: KeyModifiers::CONTROL,
 					code: KeyCode::Up,  ..
-				} => {app.change_cursor(-0x40)},
+				} => {app.change_cursor(-(4 * app.line_digits() as i64))},
 
 				KeyEvent {
 					modifiers: KeyModifiers::CONTROL,
 					code: KeyCode::Down,  ..
-				} => {app.change_cursor(0x40)},
+				} => {app.change_cursor(4 * app.line_digits() as i64)},
 
-				// Alt + Left / Right: move selection by 1 bytes
+				// Alt + Left / Right: move selection by 1 bytes.
+				// Without selection, go back and forth in the jump list
 				KeyEvent {
 					modifiers: KeyModifiers::ALT,
 					code: KeyCode::Right,  ..
-				} => {app.move_selection(0x2); continue;},
+				} => {
+					match app.selection_start {
+						Some(_) => app.move_selection(app.digits() as i64),
+						None => app.jump_forward()
+					}
+					continue;
+				},
 
 				KeyEvent {
 					modifiers: KeyModifiers::ALT,
 					code: KeyCode::Left,  ..
-				} => {app.move_selection(-0x2); continue;},
+				} => {
+					match app.selection_start {
+						Some(_) => app.move_selection(-(app.digits() as i64)),
+						None => app.jump_back()
+					}
+					continue;
+				},
+
+				// Ctrl + O: go back to the position before the last jump
+				KeyEvent {
+					modifiers: KeyModifiers::CONTROL,
+					code: KeyCode::Char('o'),  ..
+				} => {app.jump_back(); continue;},
+
+				// Ctrl + I: go forward in the jump list.
+				// Most terminals send Tab instead, use Alt + Right there
+				KeyEvent {
+					modifiers: KeyModifiers::CONTROL,
+					code: KeyCode::Char('i'),  ..
+				} => {app.jump_forward(); continue;},
 
 				// Alt + Up / Down: move selection by 1 line
 				KeyEvent {
 					modifiers: KeyModifiers::ALT,
 					code: KeyCode::Down,  ..
-				} => {app.move_selection(0x20); continue;},
+				} => {app.move_selection(app.line_digits() as i64); continue;},
 
 				KeyEvent {
 					modifiers: KeyModifiers::ALT,
 					code: KeyCode::Up,  ..
-				} => {app.move_selection(-0x20); continue;},
+				} => {app.move_selection(-(app.line_digits() as i64)); continue;},
 
 
 				// Shift + N: go to previous search result
@@ -279,6 +403,36 @@ fn handle_keyboard_inputs(mut app: App, terminal: &mut Terminal<CrosstermBackend
 					code: KeyCode::Char('u'),  ..
 				} => {app.undo_all(); continue;},
 
+				// Ctrl + X: cut the selected bytes
+				KeyEvent {
+					modifiers: KeyModifiers::CONTROL,
+					code: KeyCode::Char('x'),  ..
+				} => {
+					match app.mode {
+						Mode::Insert => {
+							if let Some(bytes) = app.delete_selection() {
+								app.clipboard = Some(bytes);
+							}
+						},
+						Mode::Overwrite => {
+							app.add_error_message(app::WarningLevel::Info,
+								"Switch to insert mode (Ctrl + J) to cut bytes".to_string());
+						}
+					}
+					continue;
+				},
+
+				// Ctrl + V: paste the bytes cut or copied
+				KeyEvent {
+					modifiers: KeyModifiers::CONTROL,
+					code: KeyCode::Char('v'),  ..
+				} => {
+					if let Some(bytes) = app.clipboard.clone() {
+						app.paste(&bytes);
+					}
+					continue;
+				},
+
 				// Ctrl + space: select the current character
 				KeyEvent {
 					modifiers: KeyModifiers::CONTROL,
@@ -294,9 +448,9 @@ fn handle_keyboard_inputs(mut app: App, terminal: &mut Terminal<CrosstermBackend
 					code: KeyCode::Home,  ..
 				} => {
 					// use the to stay on the same 'char' of the hex character
-					let cursor_on_second_char = app.cursor % 2;
+					let cursor_on_second_char = app.cursor % app.digits();
 
-					app.jump_to(app.cursor / 2 % 0x10);
+					app.jump_to(app.cursor / app.digits() % app.bytes_per_line);
 
 					app.cursor += cursor_on_second_char;
 					continue;
@@ -307,11 +461,11 @@ fn handle_keyboard_inputs(mut app: App, terminal: &mut Terminal<CrosstermBackend
 					modifiers: KeyModifiers::CONTROL,
 					code: KeyCode::End,  ..
 				} => {
-					let size_of_last_line = app.file_size % 0x10;
-					let column_of_cursor = app.cursor / 2 % 0x10;
+					let size_of_last_line = app.file_size % app.bytes_per_line;
+					let column_of_cursor = app.cursor / app.digits() % app.bytes_per_line;
 
 					// use the to stay on the same 'char' of the hex character
-					let cursor_on_second_char = app.cursor % 2;
+					let cursor_on_second_char = app.cursor % app.digits();
 
 					// we go on the last line
 					if column_of_cursor < size_of_last_line {
@@ -323,7 +477,7 @@ fn handle_keyboard_inputs(mut app: App, terminal: &mut Terminal<CrosstermBackend
 					// we go on the line just before the last one
 					else {
 						app.jump_to(
-							app.file_size - size_of_last_line - 0x10 + column_of_cursor
+							app.file_size - size_of_last_line - app.bytes_per_line + column_of_cursor
 						);
 					}
 
@@ -336,42 +490,46 @@ fn handle_keyboard_inputs(mut app: App, terminal: &mut Terminal<CrosstermBackend
 
 			match key.code {
 
+				// Select a field of the inspector
+				KeyCode::Down if app.editor_mode == CurrentEditor::Inspector => app.select_inspector_field(1),
+				KeyCode::Up if app.editor_mode == CurrentEditor::Inspector => app.select_inspector_field(-1),
+
 				// Move the cursor
 				KeyCode::Down => {
 					// if we are on the last line, also move the screen down
-					let current_line = (app.cursor.saturating_sub(app.offset * 2)) / 32;
+					let current_line = (app.cursor.saturating_sub(app.offset * app.digits())) / app.line_digits();
 
 					if current_line == (app.lines_displayed-1).into() {
-						app.change_offset(0x10)
+						app.change_offset(app.bytes_per_line as i64)
 					}
 
 					// move the cursor down
-					app.change_cursor(0x20)
+					app.change_cursor(app.line_digits() as i64)
 				},
 				KeyCode::Up => {
 					// don't change cursor if we are on the last line of the file
-					if app.cursor < 0x1f {
+					if app.cursor < app.line_digits() - 1 {
 						continue;
 					}
 
 					// if we are on the first line, also move the screen up
-					if (app.cursor.saturating_sub(app.offset*2)) / 32 == 0 {
-						app.change_offset(-0x10);
+					if (app.cursor.saturating_sub(app.offset * app.digits())) / app.line_digits() == 0 {
+						app.change_offset(-(app.bytes_per_line as i64));
 					}
 					
-					app.change_cursor(-0x20);				
+					app.change_cursor(-(app.line_digits() as i64));				
 				},
 				KeyCode::Right => {
 					match app.editor_mode {
 						CurrentEditor::HexEditor   => {app.change_cursor(1)},
-						CurrentEditor::AsciiEditor => {app.change_cursor(2)},
+						CurrentEditor::AsciiEditor | CurrentEditor::Inspector => {app.change_cursor(app.digits() as i64)},
 						_ => {}
 					};
 				},
 				KeyCode::Left => {
 					match app.editor_mode {
 						CurrentEditor::HexEditor   => {app.change_cursor(-1)},
-						CurrentEditor::AsciiEditor => {app.change_cursor(-2)},
+						CurrentEditor::AsciiEditor | CurrentEditor::Inspector => {app.change_cursor(-(app.digits() as i64))},
 						_ => {}
 					};
 				},
@@ -395,15 +553,16 @@ fn handle_keyboard_inputs(mut app: App, terminal: &mut Terminal<CrosstermBackend
 							
 							// if the previous char is the last modified, undo() instead of 
 							// just moving the cursor left
-							if let Some((_, addr_last_change, _)) = app.history.last() {
-								if *addr_last_change == (app.cursor - 1) / 2 {
+							if let Some((modification, addr_last_change, _)) = app.history.last() {
+								if *modification != Modification::TransactionEnd
+									&& *addr_last_change == (app.cursor - 1) / app.digits() {
 									app.undo();
 									continue;
 								}
 							}
 
 							match pane {
-								CurrentEditor::AsciiEditor => app.change_cursor(-2),
+								CurrentEditor::AsciiEditor => app.change_cursor(-(app.digits() as i64)),
 								CurrentEditor::HexEditor  => app.change_cursor(-1),
 								_ => {continue;} // don't handle the other cases
 							}
@@ -412,33 +571,54 @@ fn handle_keyboard_inputs(mut app: App, terminal: &mut Terminal<CrosstermBackend
 						// Delete the previous byte
 						(Mode::Insert, _) => {
 
-							match app.cursor % 2 {
+							// Delete all the selected bytes
+							if app.selection_start.is_some() {
+								app.delete_selection();
+								continue;
+							}
+
+							match app.cursor % app.digits() {
 								// Delete the previous byte 
 								0 => {
 									if app.cursor > 1 {
-										app.delete_byte((app.cursor / 2)-1);
-										app.cursor -= 2;
+										app.delete_byte((app.cursor / app.digits())-1);
+										app.cursor -= app.digits();
 									}
 								},
 								// If we are in the middle byte, delete the current byte
-								1 => {
-									app.delete_byte(app.cursor / 2);
-									app.change_cursor(-1);
-								},
-								2_u64..=u64::MAX => unreachable!("app.cursor % 2 is always 1 or 2")
+								position => {
+									app.delete_byte(app.cursor / app.digits());
+									app.change_cursor(-(position as i64));
+								}
 							}
 						}
 					}
 				},
 
 				KeyCode::Delete => {
-					if app.mode == Mode::Insert {
-						app.delete_byte(app.cursor / 2);
+					if app.mode == Mode::Insert && app.selection_start.is_some() {
+						app.delete_selection();
+					} else if app.mode == Mode::Insert {
+						app.delete_byte(app.cursor / app.digits());
 					}
 				},
 
 				// Type ascii : edit the file & shortcuts
 				KeyCode::Char(key) => {
+					// second key of `m<a-z>`: set a mark, or of `'<a-z>`: jump to a mark
+					match pending_key {
+						Some('m') => { app.set_mark(key, ""); continue; },
+						Some('\'') => { app.jump_to_mark(key); continue; },
+						_ => {}
+					}
+
+					// `'<a-z>` also jumps from the list of marks
+					if app.editor_mode == CurrentEditor::ListPopup && key == '\'' {
+						app.editor_mode = CurrentEditor::HexEditor;
+						app.pending_key = Some(key);
+						continue;
+					}
+
 					// exit the app on 'q' in Hex mode
 					if app.editor_mode == CurrentEditor::HexEditor && key == 'q' {
 						// if we don't have any changes, exit. Else show the exit popup
@@ -449,21 +629,26 @@ fn handle_keyboard_inputs(mut app: App, terminal: &mut Terminal<CrosstermBackend
 						}
 					}
 
-					// Hex editor
+					// Hex editor, the digits depend of the base of the view
 					if app.editor_mode == CurrentEditor::HexEditor
-						&&  key.is_ascii_hexdigit() {
+						&&  key.is_digit(app.view.radix()) {
 							// convert key pressed to u8 f -> 15
-							let value: u8 = key.to_digit(16)
+							let value: u8 = key.to_digit(app.view.radix())
 								.unwrap()
 								.try_into()
 								.unwrap();
 	
 							app.write(app.cursor, value);
 							app.change_cursor(1);
+
+					// ' ' toggle the bit under the cursor in the binary view
+					} else if app.editor_mode == CurrentEditor::HexEditor && app.view == View::Bin && key == ' ' {
+						app.toggle_bit();
 					
 					// 'v' Start the selection
 					} else if app.editor_mode == CurrentEditor::HexEditor && key == 'v' {
 						app.selection_start = Some(app.cursor);
+						app.preview_scroll = 0;
 						continue;
 	
 					// ':' Open Command bar
@@ -488,14 +673,40 @@ fn handle_keyboard_inputs(mut app: App, terminal: &mut Terminal<CrosstermBackend
 					} else if app.editor_mode == CurrentEditor::HexEditor && key == 'n'{
 						app.go_to_next_search_result();
 
-					// Ascii Editor
-					} else if app.editor_mode == CurrentEditor::AsciiEditor
-						&& key.is_ascii() {
-							// convert key pressed to u8 A -> 0x41
-							let value: u8 = key as u8;
-	
-							app.write_ascii(app.cursor, value);
-							app.change_cursor(2);
+					// 'm' and '\'' start the shortcuts of the marks
+					} else if app.editor_mode == CurrentEditor::HexEditor && (key == 'm' || key == '\'') {
+						app.pending_key = Some(key);
+
+					// 'p' follow the pointer at the cursor, 'P' go back
+					} else if app.editor_mode == CurrentEditor::HexEditor && key == 'p' {
+						app.follow_pointer();
+
+					} else if app.editor_mode == CurrentEditor::HexEditor && key == 'P' {
+						app.follow_back();
+
+					// '}' '{' go to the next region with a high / low entropy
+					} else if app.editor_mode == CurrentEditor::HexEditor && (key == '}' || key == '{') {
+						app.go_to_next_entropy_region(key == '}');
+
+					// '[' ']' scroll the preview of the selection
+					} else if app.editor_mode == CurrentEditor::HexEditor && key == '[' {
+						app.preview_scroll = app.preview_scroll.saturating_sub(1);
+
+					} else if app.editor_mode == CurrentEditor::HexEditor && key == ']' {
+						app.preview_scroll = app.preview_scroll.saturating_add(1);
+
+					// 'y' copy the selected bytes
+					} else if app.editor_mode == CurrentEditor::HexEditor && key == 'y' {
+						if let Some(bytes) = app.get_selected_bytes() {
+							app.add_error_message(app::WarningLevel::Info,
+								format!("Copied 0x{:x} bytes", bytes.len()));
+							app.clipboard = Some(bytes);
+						}
+
+					// Ascii Editor, the char is encoded with `app.encoding`
+					} else if app.editor_mode == CurrentEditor::AsciiEditor {
+							let written = app.write_ascii(app.cursor, key);
+							app.change_cursor((written * app.digits()) as i64);
 					
 					// Command Bar
 					} else if app.editor_mode == CurrentEditor::CommandBar {
@@ -505,6 +716,10 @@ fn handle_keyboard_inputs(mut app: App, terminal: &mut Terminal<CrosstermBackend
 							cmd_text.command.push(key);
 						}
 					
+					// 'q' close the popups
+					} else if (app.editor_mode == CurrentEditor::StatsPopup || app.editor_mode == CurrentEditor::ListPopup) && key == 'q' {
+						app.editor_mode = CurrentEditor::HexEditor;
+
 					// Exit popup
 					} else if app.editor_mode == CurrentEditor::ExitPopup {
 						if key == 'y' {
@@ -524,6 +739,12 @@ fn handle_keyboard_inputs(mut app: App, terminal: &mut Terminal<CrosstermBackend
 				// Esc: quit the command bar or the Ascii mode
 				// exit selection if defined
 				KeyCode::Esc => {
+					// close the popups, and keep the selection
+					if app.editor_mode == CurrentEditor::StatsPopup || app.editor_mode == CurrentEditor::ListPopup {
+						app.editor_mode = CurrentEditor::HexEditor;
+						continue;
+					}
+
 					// quit command bar
 					if app.editor_mode != CurrentEditor::HexEditor {
 						app.command_bar = None;
@@ -538,49 +759,59 @@ fn handle_keyboard_inputs(mut app: App, terminal: &mut Terminal<CrosstermBackend
 
 				// interpret the command, and close the command bar
 				KeyCode::Enter => {
-					if app.editor_mode == CurrentEditor::CommandBar {
+					// edit the value of the field selected in the inspector
+					if app.editor_mode == CurrentEditor::Inspector {
+						app.edit_inspector_field();
+
+					} else if app.editor_mode == CurrentEditor::CommandBar {
 						app.interpret_command();
 						app.command_bar = None;
-						app.editor_mode = CurrentEditor::HexEditor;
+
+						// the command can open a popup
+						if app.editor_mode == CurrentEditor::CommandBar {
+							app.editor_mode = CurrentEditor::HexEditor;
+						}
 					}
 				}
 
 				// Jump by a whole screen
 				KeyCode::PageDown => {
 					// we jump by a whole screen
-					let offset_to_jump = (app.lines_displayed-1) * 0x10;
+					let offset_to_jump = u64::from(app.lines_displayed-1) * app.bytes_per_line;
 					// convert to i64
-					let offset_to_jump: i64 = offset_to_jump.into();
+					let offset_to_jump = offset_to_jump as i64;
 					
 					app.change_offset(offset_to_jump);
-					app.change_cursor(offset_to_jump*2);
+					app.change_cursor(offset_to_jump * app.digits() as i64);
 				},
 
 				// Jump by a whole screen
 				KeyCode::PageUp => {
-					let offset_to_jump = (app.lines_displayed-1) * 0x10;
+					let offset_to_jump = u64::from(app.lines_displayed-1) * app.bytes_per_line;
 					// convert to i64
-					let offset_to_jump: i64 = offset_to_jump.into();
+					let offset_to_jump = offset_to_jump as i64;
 					
 					app.change_offset(-offset_to_jump);
-					app.change_cursor(-offset_to_jump*2);
+					app.change_cursor(-offset_to_jump * app.digits() as i64);
 				},
 
 				// Go to start of the line
 				KeyCode::Home => {
-					app.cursor_jump_to(app.cursor - (app.cursor % 0x20));
+					app.cursor_jump_to(app.cursor - (app.cursor % app.line_digits()));
 				},
 				
 				// Go to end of the line
 				KeyCode::End => {
-					app.cursor_jump_to(app.cursor - (app.cursor % 0x20) + 0x1f);
+					app.cursor_jump_to(app.cursor - (app.cursor % app.line_digits()) + app.line_digits() - 1);
 				},
 
-				// switch between Hex and Ascii editor
+				// switch between Hex and Ascii editor, and the inspector if it is displayed
 				KeyCode::Tab => { 
 					match app.editor_mode {
 						CurrentEditor::HexEditor   => {app.editor_mode = CurrentEditor::AsciiEditor},
+						CurrentEditor::AsciiEditor if app.show_inspector => {app.editor_mode = CurrentEditor::Inspector},
 						CurrentEditor::AsciiEditor => {app.editor_mode = CurrentEditor::HexEditor},
+						CurrentEditor::Inspector   => {app.editor_mode = CurrentEditor::HexEditor},
 						_ => {}
 					};
 				},
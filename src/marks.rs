@@ -0,0 +1,29 @@
+/// Address remembered with `m<a-z>`, to come back to it with `'<a-z>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mark {
+	pub address: u64,
+	pub label: String
+}
+
+/// Marks are named by a lowercase letter, like in vim
+pub fn is_mark_name(name: char) -> bool {
+	name.is_ascii_lowercase()
+}
+
+/// Line saved in the state file for the mark `name`: `<content hash>\t<name>\t<address>\t<label>`.
+/// The hash of the file keeps the marks of a file from being used once the file has changed
+pub fn to_entry(content_hash: &str, name: char, mark: &Mark) -> String {
+	format!("{}\t{}\t0x{:x}\t{}", content_hash, name, mark.address, mark.label)
+}
+
+/// Parse an entry written by `to_entry()`. Return the content hash, the name and the mark
+pub fn parse_entry(entry: &str) -> Option<(&str, char, Mark)> {
+	let mut fields = entry.splitn(4, '\t');
+
+	let content_hash = fields.next()?;
+	let name = fields.next()?.chars().next().filter(|name| is_mark_name(*name))?;
+	let address = u64::from_str_radix(fields.next()?.trim_start_matches("0x"), 16).ok()?;
+	let label = fields.next().unwrap_or("").to_string();
+
+	Some((content_hash, name, Mark { address, label }))
+}
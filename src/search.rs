@@ -4,7 +4,8 @@ use std::io::{BufReader, Error, Read, Seek, SeekFrom};
 #[derive(Debug, PartialEq)]
 pub enum MatchType {
     Hex,
-    Text
+    Text,
+    Pointer(usize)  // encoding of an address, found by `:xref`. The size is in bytes
 }
 
 #[derive(PartialEq)]
@@ -262,4 +263,57 @@ pub fn search_hex_ascii(mut file: File, search_ascii: &str, search_bytes: Vec<u8
     
     // TODO, make the search async, and return search_results
     // Ok(search_results)
-}
\ No newline at end of file
+}
+
+/// search several byte patterns at once in a File, i.e the encodings of a pointer.
+/// When several patterns match at the same address, only the longest one is kept
+pub fn search_patterns(mut file: File, patterns: &[Vec<u8>]) -> Result<Option<SearchResults>, std::io::Error> {
+    const CHUNK_SIZE: usize = 0x10000;
+
+    let max_len = patterns.iter().map(|pattern| pattern.len()).max().unwrap_or(0);
+    if max_len == 0 {
+        return Ok(None);
+    }
+
+    file.seek(SeekFrom::Start(0))?;
+    let mut reader = BufReader::new(file);
+
+    let mut search_results: Option<SearchResults> = None;
+
+    // address of buf[0]. The end of each chunk is kept in the next one,
+    // so the patterns across two chunks are found
+    let mut buf: Vec<u8> = vec![];
+    let mut buf_address: u64 = 0;
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+
+    loop {
+        let read_len = reader.read(&mut chunk)?;
+        let eof = read_len == 0;
+        buf.extend_from_slice(&chunk[..read_len]);
+
+        // at the end of the file, we look at every position left
+        let last_position = match eof {
+            true => buf.len(),
+            false => buf.len().saturating_sub(max_len - 1)
+        };
+
+        for position in 0..last_position {
+            let longest_match = patterns.iter()
+                .filter(|pattern| buf[position..].starts_with(pattern))
+                .map(|pattern| pattern.len())
+                .max();
+
+            if let Some(len) = longest_match {
+                let address = buf_address + position as u64;
+                search_results = add_to_search_results(address, MatchType::Pointer(len), search_results, len);
+            }
+        }
+
+        if eof {
+            return Ok(search_results);
+        }
+
+        buf.drain(..last_position);
+        buf_address += last_position as u64;
+    }
+}
@@ -0,0 +1,64 @@
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// Directory where we remember things between two sessions, like the checksum presets.
+/// `$XDG_STATE_HOME/hextazy`, or `~/.local/state/hextazy` by default
+fn state_directory() -> Option<PathBuf> {
+	let state_home = match env::var_os("XDG_STATE_HOME") {
+		Some(state_home) if !state_home.is_empty() => PathBuf::from(state_home),
+		_ => PathBuf::from(env::var_os("HOME")?).join(".local/state")
+	};
+
+	Some(state_home.join("hextazy"))
+}
+
+/// The files are identified by their absolute path
+fn file_key(file_path: &str) -> String {
+	fs::canonicalize(file_path)
+		.map(|path| path.to_string_lossy().to_string())
+		.unwrap_or(file_path.to_string())
+}
+
+/// Read the entries saved for `file_path` in the state file `name`.
+/// Each line of a state file is `<file path>\t<entry>`
+pub fn load_entries(name: &str, file_path: &str) -> Vec<String> {
+	let Some(state_file) = state_directory().map(|directory| directory.join(name)) else {
+		return vec![];
+	};
+
+	let key = file_key(file_path);
+
+	fs::read_to_string(state_file)
+		.unwrap_or_default()
+		.lines()
+		.filter_map(|line| line.split_once('\t'))
+		.filter(|(path, _)| *path == key)
+		.map(|(_, entry)| entry.to_string())
+		.collect()
+}
+
+/// Replace the entries saved for `file_path` in the state file `name`
+pub fn save_entries(name: &str, file_path: &str, entries: &[String]) -> Result<(), std::io::Error> {
+	let directory = state_directory()
+		.ok_or(std::io::Error::new(ErrorKind::NotFound, "No $HOME or $XDG_STATE_HOME directory"))?;
+	let state_file = directory.join(name);
+
+	let key = file_key(file_path);
+
+	// keep the entries of the other files
+	let mut content: String = fs::read_to_string(&state_file)
+		.unwrap_or_default()
+		.lines()
+		.filter(|line| line.split_once('\t').is_some_and(|(path, _)| path != key))
+		.map(|line| format!("{}\n", line))
+		.collect();
+
+	for entry in entries {
+		content.push_str(&format!("{}\t{}\n", key, entry));
+	}
+
+	fs::create_dir_all(&directory)?;
+	fs::write(state_file, content)
+}
@@ -0,0 +1,252 @@
+use std::cmp::{min, max};
+
+/// Classes of bytes, each one has its own color in the interface
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ByteClass {
+	Null,
+	Whitespace,
+	Printable,
+	OtherAscii,
+	High		// 0x80 and above
+}
+
+impl ByteClass {
+	pub const ALL: [ByteClass; 5] = [
+		ByteClass::Null, ByteClass::Whitespace, ByteClass::Printable, ByteClass::OtherAscii, ByteClass::High
+	];
+
+	pub fn of(val: u8) -> ByteClass {
+		match val {
+			0x00 => ByteClass::Null,
+			val if val.is_ascii_whitespace() => ByteClass::Whitespace,
+			val if val > 0x20 && val < 0x7f => ByteClass::Printable,
+			val if val.is_ascii() => ByteClass::OtherAscii,
+			_ => ByteClass::High
+		}
+	}
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			ByteClass::Null => "null",
+			ByteClass::Whitespace => "whitespace",
+			ByteClass::Printable => "printable",
+			ByteClass::OtherAscii => "other ascii",
+			ByteClass::High => "high bytes"
+		}
+	}
+}
+
+/// Entropy above which a block is considered compressed or encrypted, in bits per byte
+pub const HIGH_ENTROPY: f64 = 7.0;
+/// Entropy under which a block is considered mostly padding or repeated values
+pub const LOW_ENTROPY: f64 = 2.0;
+
+/// Shannon entropy of bytes, in bits per byte (between 0 and 8), from the count of each byte value
+pub fn entropy(counts: &[u64; 256]) -> f64 {
+	let total: u64 = counts.iter().sum();
+
+	if total == 0 {
+		return 0.0;
+	}
+
+	counts.iter()
+		.filter(|count| **count > 0)
+		.map(|count| {
+			let p = *count as f64 / total as f64;
+			-p * p.log2()
+		})
+		.sum()
+}
+
+/// Class with the most bytes, from the count of each byte value
+pub fn dominant_class(counts: &[u64; 256]) -> ByteClass {
+	let mut class_counts = [0u64; ByteClass::ALL.len()];
+
+	for (byte, count) in counts.iter().enumerate() {
+		class_counts[ByteClass::of(byte as u8) as usize] += count;
+	}
+
+	// the first class wins if several classes have as many bytes
+	ByteClass::ALL.into_iter()
+		.rev()
+		.max_by_key(|class| class_counts[*class as usize])
+		.unwrap_or(ByteClass::Null)
+}
+
+/// Number of bytes summarized by each line of the entropy pane and the minimap,
+/// to display the whole file in `lines` lines
+pub fn line_size(file_size: u64, lines: u64) -> u64 {
+	file_size.div_ceil(lines.max(1)).max(1)
+}
+
+/// Entropy and classes of bytes of the whole file, split in blocks. Used by the entropy pane,
+/// the minimap, and to jump to the compressed or encrypted regions of the file
+pub struct FileMap {
+	pub block_size: u64,
+	pub entropies: Vec<f64>,	// entropy of each block
+	pub classes: Vec<ByteClass>,	// class with the most bytes in each block
+	pub edited: Option<(u64, u64)>	// bytes changed since the map was computed (start, end excluded)
+}
+
+impl FileMap {
+	/// Maximum number of blocks, so big files are computed quickly
+	pub const MAX_BLOCKS: u64 = 0x400;
+	/// Number of bytes read to compute the entropy of the big blocks
+	pub const SAMPLE_SIZE: u64 = 0x1000;
+	/// The bytes read in the big blocks are split in slices spread over the block
+	pub const SAMPLE_SLICES: u64 = 0x10;
+	/// Smallest block, there are not enough bytes under it for the entropy to mean something
+	pub const MIN_BLOCK_SIZE: u64 = 0x100;
+
+	/// Size of the blocks used for a file of `file_size` bytes
+	pub fn block_size(file_size: u64) -> u64 {
+		file_size.div_ceil(FileMap::MAX_BLOCKS).max(FileMap::MIN_BLOCK_SIZE)
+	}
+
+	/// Ranges of the bytes read to compute the block from `start` to `end` (excluded).
+	/// The big blocks are sampled with slices spread from their start to their end
+	pub fn sample_ranges(start: u64, end: u64) -> Vec<(u64, u64)> {
+		if end - start <= FileMap::SAMPLE_SIZE {
+			return vec![(start, end)];
+		}
+
+		let slice_size = FileMap::SAMPLE_SIZE / FileMap::SAMPLE_SLICES;
+		let stride = (end - start - slice_size) / (FileMap::SAMPLE_SLICES - 1);
+
+		(0..FileMap::SAMPLE_SLICES)
+			.map(|slice| start + slice * stride)
+			.map(|slice_start| (slice_start, slice_start + slice_size))
+			.collect()
+	}
+
+	/// Remember that the bytes from `start` to `end` (excluded) changed, their blocks are computed again
+	pub fn edit(&mut self, start: u64, end: u64) {
+		self.edited = Some(match self.edited {
+			Some((edited_start, edited_end)) => (min(edited_start, start), max(edited_end, end)),
+			None => (start, end)
+		});
+	}
+
+	/// Highest entropy of the blocks between the addresses `start` and `end` (excluded)
+	pub fn max_entropy(&self, start: u64, end: u64) -> Option<f64> {
+		let first_block = (start / self.block_size) as usize;
+		let last_block = (end.saturating_sub(1) / self.block_size) as usize;
+
+		self.entropies.get(first_block..=last_block.min(self.entropies.len().saturating_sub(1)))?
+			.iter()
+			.copied()
+			.reduce(f64::max)
+	}
+
+	/// Class with the most blocks between the addresses `start` and `end` (excluded)
+	pub fn dominant_class(&self, start: u64, end: u64) -> Option<ByteClass> {
+		let first_block = (start / self.block_size) as usize;
+		let last_block = (end.saturating_sub(1) / self.block_size) as usize;
+
+		let blocks = self.classes.get(first_block..=last_block.min(self.classes.len().saturating_sub(1)))?;
+
+		let mut class_counts = [0usize; ByteClass::ALL.len()];
+		for class in blocks {
+			class_counts[*class as usize] += 1;
+		}
+
+		ByteClass::ALL.into_iter()
+			.rev()
+			.filter(|class| class_counts[*class as usize] > 0)
+			.max_by_key(|class| class_counts[*class as usize])
+	}
+
+	/// Address of the next region after `address` whose blocks match `in_region`.
+	/// We skip the region `address` is in, to go to the start of the next one
+	pub fn next_region(&self, address: u64, in_region: impl Fn(f64) -> bool) -> Option<u64> {
+		let current_block = (address / self.block_size) as usize;
+
+		let mut previous_in_region = self.entropies.get(current_block).is_some_and(|entropy| in_region(*entropy));
+
+		for (block, entropy) in self.entropies.iter().enumerate().skip(current_block + 1) {
+			let is_in_region = in_region(*entropy);
+
+			if is_in_region && !previous_in_region {
+				return Some(block as u64 * self.block_size);
+			}
+			previous_in_region = is_in_region;
+		}
+
+		None
+	}
+}
+
+/// Statistics on the bytes of the selection or the file, displayed by `:stats`
+pub struct ByteStats {
+	pub start: u64,
+	pub end: u64,			// excluded
+	pub description: &'static str,	// "the selection" or "the file"
+	pub counts: [u64; 256]		// number of occurrences of each byte value
+}
+
+impl ByteStats {
+	pub fn total(&self) -> u64 {
+		self.counts.iter().sum()
+	}
+
+	pub fn min(&self) -> Option<u8> {
+		self.counts.iter().position(|count| *count > 0).map(|byte| byte as u8)
+	}
+
+	pub fn max(&self) -> Option<u8> {
+		self.counts.iter().rposition(|count| *count > 0).map(|byte| byte as u8)
+	}
+
+	pub fn mean(&self) -> Option<f64> {
+		let sum: u64 = self.counts.iter()
+			.enumerate()
+			.map(|(byte, count)| byte as u64 * count)
+			.sum();
+
+		match self.total() {
+			0 => None,
+			total => Some(sum as f64 / total as f64)
+		}
+	}
+
+	pub fn entropy(&self) -> f64 {
+		entropy(&self.counts)
+	}
+
+	/// Number of bytes in `class`
+	pub fn class_count(&self, class: ByteClass) -> u64 {
+		(0..=255u8)
+			.filter(|byte| ByteClass::of(*byte) == class)
+			.map(|byte| self.counts[byte as usize])
+			.sum()
+	}
+
+	/// The `n` most common byte values, with their number of occurrences
+	pub fn most_common(&self, n: usize) -> Vec<(u8, u64)> {
+		let mut bytes: Vec<(u8, u64)> = (0..=255u8)
+			.map(|byte| (byte, self.counts[byte as usize]))
+			.filter(|(_, count)| *count > 0)
+			.collect();
+
+		// the most common first, the smallest value first if they are as common
+		bytes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+		bytes.truncate(n);
+		bytes
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn sample_ranges_cover_the_block() {
+		assert_eq!(FileMap::sample_ranges(0x100, 0x200), vec![(0x100, 0x200)]);
+
+		let ranges = FileMap::sample_ranges(0, 0x10_0000);
+		assert_eq!(ranges.len() as u64, FileMap::SAMPLE_SLICES);
+		assert_eq!(ranges.iter().map(|(start, end)| end - start).sum::<u64>(), FileMap::SAMPLE_SIZE);
+		assert_eq!(ranges.first(), Some(&(0, 0x100)));
+		assert_eq!(ranges.last(), Some(&(0xfff00, 0x10_0000)));
+	}
+}
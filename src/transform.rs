@@ -0,0 +1,396 @@
+use std::cmp::min;
+
+use crate::expression::{parse_number, Endianness};
+
+/// Convert a string of hex digits (i.e `"9090"` or `"0x9090"`) to a vector of bytes.
+/// Return None if the string is empty, has an odd length or contains non hex chars
+pub fn parse_hex_bytes(hex_string: &str) -> Option<Vec<u8>> {
+	let hex_string = hex_string.strip_prefix("0x")
+		.or(hex_string.strip_prefix("0X"))
+		.unwrap_or(hex_string);
+
+	if hex_string.is_empty() || !hex_string.len().is_multiple_of(2) {
+		return None;
+	}
+
+	let mut bytes: Vec<u8> = vec![];
+
+	for i in (0..hex_string.len()).step_by(2) {
+		let byte = u8::from_str_radix(hex_string.get(i..i+2)?, 16).ok()?;
+		bytes.push(byte);
+	}
+
+	Some(bytes)
+}
+
+/// Repeat `pattern` until we have `len` bytes. The last repetition is truncated if needed
+pub fn fill_pattern(pattern: &[u8], len: usize) -> Vec<u8> {
+	pattern.iter()
+		.copied()
+		.cycle()
+		.take(len)
+		.collect()
+}
+
+/// Generate `len` incrementing bytes starting at `start`, i.e `00 01 02 ...`.
+/// We wrap to 0x00 after 0xff
+pub fn fill_incrementing(start: u8, len: usize) -> Vec<u8> {
+	(0..len)
+		.map(|i| start.wrapping_add(i as u8))
+		.collect()
+}
+
+/// Generate `len` pseudo-random bytes. The same `seed` always give the same bytes
+pub fn fill_random(seed: u64, len: usize) -> Vec<u8> {
+	let mut rng = Xorshift::new(seed);
+
+	let mut bytes: Vec<u8> = Vec::with_capacity(len);
+
+	while bytes.len() < len {
+		let random = rng.next_u64().to_le_bytes();
+		let remaining = min(len - bytes.len(), random.len());
+		bytes.extend_from_slice(&random[..remaining]);
+	}
+
+	bytes
+}
+
+/// Escape the bytes like in a C string, i.e `hi\n\0\x90`.
+/// Printable ascii is kept as is
+pub fn escape_bytes(bytes: &[u8]) -> String {
+	let mut escaped = String::with_capacity(bytes.len());
+
+	for byte in bytes {
+		match byte {
+			b'\n' => escaped.push_str("\\n"),
+			b'\r' => escaped.push_str("\\r"),
+			b'\t' => escaped.push_str("\\t"),
+			b'\0' => escaped.push_str("\\0"),
+			b'\\' => escaped.push_str("\\\\"),
+			b'"'  => escaped.push_str("\\\""),
+			0x20..=0x7e => escaped.push(*byte as char),
+			_ => escaped.push_str(&format!("\\x{:02x}", byte))
+		}
+	}
+
+	escaped
+}
+
+/// Small pseudo-random generator (xorshift64*), good enough to generate junk bytes.
+/// This is NOT a cryptographically secure generator.
+pub struct Xorshift {
+	state: u64
+}
+
+impl Xorshift {
+	pub fn new(seed: u64) -> Xorshift {
+		// xorshift is stuck on 0 if the state is 0, so we mix the seed (splitmix64)
+		let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+		z ^= z >> 31;
+
+		Xorshift {
+			state: if z == 0 { 0x9e3779b97f4a7c15 } else { z }
+		}
+	}
+
+	pub fn next_u64(&mut self) -> u64 {
+		let mut x = self.state;
+		x ^= x >> 12;
+		x ^= x << 25;
+		x ^= x >> 27;
+		self.state = x;
+
+		x.wrapping_mul(0x2545f4914f6cdd1d)
+	}
+}
+
+/// Transformations that can be applied to the selected bytes
+#[derive(Debug, Clone, PartialEq)]
+pub enum Transform {
+	Xor(Vec<u8>),		// the key is repeated over the selection
+	And(Vec<u8>),
+	Or(Vec<u8>),
+	Not,
+	ShiftLeft(u32),		// shifts and rotations are applied to each byte
+	ShiftRight(u32),
+	RotateLeft(u32),
+	RotateRight(u32),
+	Reverse,			// reverse the order of the bytes
+	SwapNibbles,		// 0x12 -> 0x21
+	ByteSwap(usize),	// swap the endianness of each word of 2, 4 or 8 bytes
+	Arithmetic {		// add, sub or mul the selection interpreted as words (u16, u32...)
+		operation: Operation,
+		word: Word,
+		value: u64,
+		saturate: bool	// saturate instead of wrapping around
+	}
+}
+
+/// Arithmetic operations for Transform::Arithmetic
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operation {
+	Add,
+	Sub,
+	Mul
+}
+
+/// Unsigned integer of `size` bytes: u8, u16, u32 or u64
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Word {
+	pub size: usize,
+	pub endianness: Endianness
+}
+
+impl Word {
+
+	/// Parse a word type: `u8`, `u16le`, `u32be`, ... Little endian is used if not specified
+	pub fn parse(name: &str) -> Option<Word> {
+		let (name, endianness) = match (name.strip_suffix("le"), name.strip_suffix("be")) {
+			(Some(name), _) => (name, Endianness::Little),
+			(_, Some(name)) => (name, Endianness::Big),
+			_ => (name, Endianness::Little)
+		};
+
+		let size = match name {
+			"u8"  => 1,
+			"u16" => 2,
+			"u32" => 4,
+			"u64" => 8,
+			_ => return None
+		};
+
+		Some(Word { size, endianness })
+	}
+
+	/// Largest value we can store in the word
+	pub fn max(&self) -> u64 {
+		u64::MAX >> (64 - self.size * 8)
+	}
+
+	/// Read a word from `bytes`. `bytes` should contain `self.size` bytes
+	pub fn read(&self, bytes: &[u8]) -> u64 {
+		let mut buffer: [u8; 8] = [0; 8];
+
+		match self.endianness {
+			Endianness::Little => {
+				buffer[..self.size].copy_from_slice(&bytes[..self.size]);
+				u64::from_le_bytes(buffer)
+			},
+			Endianness::Big => {
+				buffer[8 - self.size..].copy_from_slice(&bytes[..self.size]);
+				u64::from_be_bytes(buffer)
+			}
+		}
+	}
+
+	/// Convert `value` to `self.size` bytes
+	pub fn encode(&self, value: u64) -> Vec<u8> {
+		match self.endianness {
+			Endianness::Little => value.to_le_bytes()[..self.size].to_vec(),
+			Endianness::Big => value.to_be_bytes()[8 - self.size..].to_vec()
+		}
+	}
+}
+
+impl Operation {
+
+	/// Compute `word <operation> value` for a word whose largest value is `max`
+	fn apply(&self, word: u64, value: u64, max: u64, saturate: bool) -> u64 {
+		match (self, saturate) {
+			(Operation::Add, false) => word.wrapping_add(value) & max,
+			(Operation::Sub, false) => word.wrapping_sub(value) & max,
+			(Operation::Mul, false) => word.wrapping_mul(value) & max,
+			(Operation::Add, true) => min(word as u128 + value as u128, max as u128) as u64,
+			(Operation::Sub, true) => word.saturating_sub(value),
+			(Operation::Mul, true) => min(word as u128 * value as u128, max as u128) as u64,
+		}
+	}
+}
+
+impl Transform {
+
+	/// Parse a transformation from a command, i.e `:xor 4142` or `:rol 3`
+	pub fn parse(command: &str) -> Result<Transform, String> {
+		let command = command.strip_prefix(':').unwrap_or(command);
+		let mut arguments = command.split_whitespace();
+
+		let name = arguments.next().unwrap_or_default();
+
+		// :add, :sub and :mul have their own arguments
+		let operation = match name {
+			"add" => Some(Operation::Add),
+			"sub" => Some(Operation::Sub),
+			"mul" => Some(Operation::Mul),
+			_ => None
+		};
+
+		if let Some(operation) = operation {
+			return Transform::parse_arithmetic(name, operation, arguments.collect());
+		}
+
+		let argument = arguments.next();
+
+		if arguments.next().is_some() {
+			return Err(format!("Too many arguments for :{}", name));
+		}
+
+		// parse the key of :xor, :and and :or
+		let key = || -> Result<Vec<u8>, String> {
+			argument.and_then(parse_hex_bytes)
+				.ok_or(format!("Usage: :{} <hex key>, i.e `:{} 4142`", name, name))
+		};
+
+		// parse the number of bits of the shifts and rotations
+		let bits = || -> Result<u32, String> {
+			argument.and_then(|bits| bits.parse::<u32>().ok())
+				.ok_or(format!("Usage: :{} <number of bits>, i.e `:{} 1`", name, name))
+		};
+
+		let no_argument = |transform: Transform| -> Result<Transform, String> {
+			match argument {
+				None => Ok(transform),
+				Some(_) => Err(format!(":{} doesn't take arguments", name))
+			}
+		};
+
+		match name {
+			"xor" => Ok(Transform::Xor(key()?)),
+			"and" => Ok(Transform::And(key()?)),
+			"or"  => Ok(Transform::Or(key()?)),
+			"not" => no_argument(Transform::Not),
+			"shl" => Ok(Transform::ShiftLeft(bits()?)),
+			"shr" => Ok(Transform::ShiftRight(bits()?)),
+			"rol" => Ok(Transform::RotateLeft(bits()?)),
+			"ror" => Ok(Transform::RotateRight(bits()?)),
+			"reverse" => no_argument(Transform::Reverse),
+			"swapnibbles" => no_argument(Transform::SwapNibbles),
+			"bswap" => match argument {
+				Some("2") => Ok(Transform::ByteSwap(2)),
+				Some("4") => Ok(Transform::ByteSwap(4)),
+				Some("8") => Ok(Transform::ByteSwap(8)),
+				_ => Err("Usage: :bswap <2|4|8>, i.e `:bswap 4` to swap the endianness of 32 bits words".to_string())
+			},
+			_ => Err(format!("Unknown transformation :{}", name))
+		}
+	}
+
+	/// Parse the arguments of :add, :sub and :mul, i.e `:add u32le 0x1000 sat`
+	fn parse_arithmetic(name: &str, operation: Operation, arguments: Vec<&str>) -> Result<Transform, String> {
+		let usage = format!("Usage: :{} <u8|u16|u32|u64>[le|be] <value> [wrap|sat], i.e `:{} u32le 0x1000`", name, name);
+
+		let (word, value, saturate) = match arguments.as_slice() {
+			[word, value] => (word, value, false),
+			[word, value, "wrap"] => (word, value, false),
+			[word, value, "sat"] | [word, value, "saturate"] => (word, value, true),
+			_ => return Err(usage)
+		};
+
+		let word = Word::parse(word).ok_or(usage.clone())?;
+		let value = parse_number(value).ok_or(usage)?;
+
+		if value > word.max() {
+			return Err(format!("0x{:x} doesn't fit in a {} bytes word", value, word.size));
+		}
+
+		Ok(Transform::Arithmetic { operation, word, value, saturate })
+	}
+
+	/// Number of bytes the transformation works on: the size of a word for arithmetic
+	/// operations, 1 byte for the other transformations
+	pub fn word_size(&self) -> usize {
+		match self {
+			Transform::Arithmetic { word, .. } => word.size,
+			Transform::ByteSwap(size) => *size,
+			_ => 1
+		}
+	}
+
+	/// Return the result of the transformation applied to `bytes`
+	pub fn apply(&self, bytes: &[u8]) -> Vec<u8> {
+		match self {
+			Transform::Xor(key) => apply_key(bytes, key, |byte, key| byte ^ key),
+			Transform::And(key) => apply_key(bytes, key, |byte, key| byte & key),
+			Transform::Or(key)  => apply_key(bytes, key, |byte, key| byte | key),
+			Transform::Not => bytes.iter().map(|byte| !byte).collect(),
+			Transform::ShiftLeft(bits)  => bytes.iter().map(|byte| byte.checked_shl(*bits).unwrap_or(0)).collect(),
+			Transform::ShiftRight(bits) => bytes.iter().map(|byte| byte.checked_shr(*bits).unwrap_or(0)).collect(),
+			Transform::RotateLeft(bits)  => bytes.iter().map(|byte| byte.rotate_left(*bits)).collect(),
+			Transform::RotateRight(bits) => bytes.iter().map(|byte| byte.rotate_right(*bits)).collect(),
+			Transform::Reverse => bytes.iter().rev().copied().collect(),
+			Transform::SwapNibbles => bytes.iter().map(|byte| byte.rotate_left(4)).collect(),
+			Transform::ByteSwap(size) => {
+				bytes.chunks(*size)
+					.flat_map(|chunk| match chunk.len() == *size {
+						true => chunk.iter().rev().copied().collect::<Vec<u8>>(),
+						false => chunk.to_vec() // incomplete word at the end, don't change it
+					})
+					.collect()
+			},
+			Transform::Arithmetic { operation, word, value, saturate } => {
+				bytes.chunks(word.size)
+					.flat_map(|chunk| match chunk.len() == word.size {
+						true => {
+							let result = operation.apply(word.read(chunk), *value, word.max(), *saturate);
+							word.encode(result)
+						},
+						false => chunk.to_vec() // incomplete word at the end, don't change it
+					})
+					.collect()
+			}
+		}
+	}
+
+	/// The first `len` bytes of `apply(bytes)`, without transforming the rest of `bytes`.
+	/// Used by the preview pane, which only shows the start of a big selection
+	pub fn apply_start(&self, bytes: &[u8], len: usize) -> Vec<u8> {
+		let len = min(len, bytes.len());
+
+		match self {
+			Transform::Reverse => bytes[bytes.len() - len..].iter().rev().copied().collect(),
+			_ => {
+				// the words start at the beginning of the selection, so only whole words are cut
+				let words_len = min(len.div_ceil(self.word_size()) * self.word_size(), bytes.len());
+
+				let mut transformed = self.apply(&bytes[..words_len]);
+				transformed.truncate(len);
+				transformed
+			}
+		}
+	}
+}
+
+/// Combine each byte with the key, the key is repeated if it is shorter than `bytes`
+fn apply_key(bytes: &[u8], key: &[u8], operation: fn(u8, u8) -> u8) -> Vec<u8> {
+	bytes.iter()
+		.zip(key.iter().cycle())
+		.map(|(byte, key)| operation(*byte, *key))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn apply_start_matches_apply() {
+		let bytes: Vec<u8> = (0..23).collect();
+
+		let transforms = [
+			"xor 4142", "not", "rol 3", "reverse", "swapnibbles", "bswap 4", "add u32le 0x1000", "mul u16be 3 sat"
+		];
+
+		for command in transforms {
+			let transform = Transform::parse(&format!(":{}", command)).unwrap();
+			let expected = transform.apply(&bytes);
+
+			for len in [0, 1, 3, 4, 5, 22, 23, 100] {
+				assert_eq!(
+					transform.apply_start(&bytes, len), expected[..len.min(bytes.len())],
+					":{} on {} bytes", command, len
+				);
+			}
+		}
+	}
+}
@@ -1,13 +1,13 @@
-use std::{i128, u128};
+use std::{cmp::min, i128, u128};
 
 use ratatui::{
 	layout::{Constraint, Direction, Layout, Rect},
 	style::{Color, Style, Stylize},
 	symbols, text::{Line, Span, Text},
-	widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+	widgets::{Bar, BarChart, Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
 	Frame
 };
-use crate::{app::{Braille, CurrentEditor, Mode, WarningLevel}, App};
+use crate::{app::{Braille, Columns, CurrentEditor, ListPopup, Mode, WarningLevel}, encoding::{Encoding, Glyph}, expression::Endianness, inspector::{self, Kind}, stats::{self, ByteClass, ByteStats}, transform::{escape_bytes, Transform}, App};
 mod braille;
 use crate::ui::braille::BRAILLE_CHARSET;
 
@@ -16,18 +16,62 @@ pub fn ui(f: &mut Frame, app: &mut App) {
 	// Clear the screen
 	f.render_widget(Clear, f.area());
 
+	let entropy_pane_width = match app.show_entropy {
+		true => 3,
+		false => 0 // No entropy pane
+	};
+
+	let minimap_pane_width = match app.show_minimap {
+		true => 5,
+		false => 0 // No minimap
+	};
+
+	let inspector_pane_width = match app.show_inspector {
+		true => 40,
+		false => 0 // No inspector
+	};
+
+	// make room for the marks before the addresses
+	let address_pane_width = match app.marks.is_empty() {
+		true => 1,
+		false => 3
+	} + app.address_width() as u16;
+
+	// fit as many bytes as we can on a line, the room for the preview pane is kept
+	// so the lines don't change when we select some bytes.
+	// It is only computed again when the terminal is resized, or the panes or the groups change
+	if app.columns == Columns::Auto {
+		let available_width = f.area().width
+			.saturating_sub(entropy_pane_width + address_pane_width + minimap_pane_width + inspector_pane_width + 33);
+		let layout = (available_width, app.group_size, app.digits());
+
+		if app.auto_columns_layout != Some(layout) {
+			app.set_bytes_per_line(auto_bytes_per_line(available_width, app.group_size, app.digits()));
+			app.auto_columns_layout = Some(layout);
+		}
+	}
+
+	let hex_pane_width = hex_pane_width(app.bytes_per_line, app.group_size, app.digits());
+	let ascii_pane_width = ascii_pane_width(app.bytes_per_line);
+
 	let contraints = match app.selection_start.is_some() {
 		true => [
-			Constraint::Max(9),
-			Constraint::Length(53),
-			Constraint::Length(18),
-			Constraint::Length(33) // We have a preview pane
+			Constraint::Length(entropy_pane_width),
+			Constraint::Max(address_pane_width),
+			Constraint::Length(hex_pane_width),
+			Constraint::Length(ascii_pane_width),
+			Constraint::Length(minimap_pane_width),
+			Constraint::Length(33), // We have a preview pane
+			Constraint::Length(inspector_pane_width)
 		],
 		false => [
-			Constraint::Max(9),
-			Constraint::Length(53),
-			Constraint::Length(18),
-			Constraint::Length(0) // No preview pane
+			Constraint::Length(entropy_pane_width),
+			Constraint::Max(address_pane_width),
+			Constraint::Length(hex_pane_width),
+			Constraint::Length(ascii_pane_width),
+			Constraint::Length(minimap_pane_width),
+			Constraint::Length(0), // No preview pane
+			Constraint::Length(inspector_pane_width)
 		]
 	};
 
@@ -39,20 +83,35 @@ pub fn ui(f: &mut Frame, app: &mut App) {
 	// update the number of lines displayed by the app.
 	// we use this for shortcuts.
 	// -2 because we don't need the 2 lines of border
-	app.lines_displayed = (panes[1].height - 2).into();
+	app.lines_displayed = (panes[2].height - 2).into();
+
+	/* Entropy Block, if enabled */
+	if app.show_entropy {
+		render_entropy_block(app, panes[0], f);
+	}
 
 	/* Adress Block */
-	render_address_block(app, panes[0], f);
+	render_address_block(app, panes[1], f);
 
 	/* Hex Block */
-	render_hex_block(app, panes[1], f);
+	render_hex_block(app, panes[2], f);
 	
 	/* Create ASCII Block */
-	render_ascii_block(app, panes[2], f);
+	render_ascii_block(app, panes[3], f);
+
+	/* Minimap Block, if enabled */
+	if app.show_minimap {
+		render_minimap_block(app, panes[4], f);
+	}
 
 	/* Render the preview Block if some bytes are selected */
 	if app.selection_start.is_some() {
-		render_preview_block(app, panes[3], f);
+		render_preview_block(app, panes[5], f);
+	}
+
+	/* Inspector Block, if enabled */
+	if app.show_inspector {
+		render_inspector_block(app, panes[6], f);
 	}
 	
 
@@ -99,17 +158,187 @@ pub fn ui(f: &mut Frame, app: &mut App) {
 		exit_popup(f);
 	}
 
+	if app.editor_mode == CurrentEditor::StatsPopup {
+		if let Some(byte_stats) = &app.byte_stats {
+			stats_popup(byte_stats, app.base_address, f);
+		}
+	}
+
+	if app.editor_mode == CurrentEditor::ListPopup {
+		if let Some(list_popup) = &app.list_popup {
+			list_popup_block(list_popup, f);
+		}
+	}
+
+}
+
+/// Width of the hex pane: `digits` chars for each byte, a space before each group of bytes,
+/// a `┊` separator every 8 bytes, and the borders
+fn hex_pane_width(bytes_per_line: u64, group_size: u64, digits: u64) -> u16 {
+	let groups = bytes_per_line.div_ceil(group_size);
+	let separators = (bytes_per_line - 1) / 8;
+	(bytes_per_line * digits + groups + separators * 2 + 3) as u16
+}
+
+/// Width of the ascii pane: 1 char for each byte, a `┊` separator every 8 bytes, and the border
+fn ascii_pane_width(bytes_per_line: u64) -> u16 {
+	let separators = (bytes_per_line - 1) / 8;
+	(bytes_per_line + separators + 1) as u16
+}
+
+/// Most bytes per line, by groups of 8, that fit in `width` chars with the hex and ascii panes
+fn auto_bytes_per_line(width: u16, group_size: u64, digits: u64) -> u64 {
+	let groups = (1..=Columns::MAX / 8)
+		.take_while(|groups| hex_pane_width(groups * 8, group_size, digits) + ascii_pane_width(groups * 8) <= width)
+		.last()
+		.unwrap_or(1);
+
+	groups * 8
+}
+
+/// Render the entropy of the whole file on the left: one line for each part of the file.
+/// The lines displayed in the hex pane are highlighted
+fn render_entropy_block(app: &mut App, pane: Rect, f: &mut Frame) {
+	let entropy_block = Block::default()
+		.borders(Borders::TOP | Borders::LEFT | Borders::BOTTOM)
+		.style(Style::default());
+
+	app.update_file_map();
+	let Some(file_map) = &app.file_map else {
+		return;
+	};
+
+	// split the file between the lines of the pane
+	let height = u64::from(pane.height.saturating_sub(2)).max(1);
+	let line_size = stats::line_size(app.file_size, height);
+
+	let viewport_start = app.offset;
+	let viewport_end = app.offset + u64::from(app.lines_displayed) * app.bytes_per_line;
+
+	let mut list_items = Vec::<ListItem>::new();
+
+	for line_start in (0..app.file_size).step_by(line_size as usize).take(height as usize) {
+		let line_end = line_start + line_size;
+		let entropy = file_map.max_entropy(line_start, line_end).unwrap_or(0.0);
+
+		let mut style = Style::default().fg(entropy_color(entropy));
+		if line_start < viewport_end && viewport_start < line_end {
+			style = style.bg(Color::Indexed(238));
+		}
+
+		list_items.push(ListItem::new(Line::from(
+			Span::styled(format!("{} ", entropy_bar(entropy)), style)
+		)));
+	}
+
+	let list = List::new(list_items).block(entropy_block);
+	f.render_widget(list, pane);
+}
+
+/// Render a map of the whole file on the right: each line has the color of the bytes
+/// the most present in its part of the file. The search results and the modifications are marked.
+fn render_minimap_block(app: &mut App, pane: Rect, f: &mut Frame) {
+	let minimap_block = Block::default()
+		.borders(Borders::TOP | Borders::RIGHT | Borders::BOTTOM)
+		.style(Style::default());
+
+	// remember where the minimap is, to handle the clicks
+	app.minimap_area = minimap_block.inner(pane);
+
+	app.update_file_map();
+	let Some(file_map) = &app.file_map else {
+		return;
+	};
+
+	// split the file between the lines of the pane
+	let height = u64::from(app.minimap_area.height).max(1);
+	let line_size = stats::line_size(app.file_size, height);
+
+	let mut search_lines = vec![false; height as usize];
+	if let Some(search_results) = &app.search_results {
+		for (address, _) in &search_results.match_addresses {
+			if let Some(line) = search_lines.get_mut((address / line_size) as usize) {
+				*line = true;
+			}
+		}
+	}
+
+	let mut modified_lines = vec![false; height as usize];
+	for (start, end) in app.modified_ranges() {
+		let last_line = min(end.saturating_sub(1) / line_size, height - 1);
+
+		for line in (start / line_size)..=last_line {
+			modified_lines[line as usize] = true;
+		}
+	}
+
+	let viewport_start = app.offset;
+	let viewport_end = app.offset + u64::from(app.lines_displayed) * app.bytes_per_line;
+
+	let mut list_items = Vec::<ListItem>::new();
+
+	for (line, line_start) in (0..app.file_size).step_by(line_size as usize).take(height as usize).enumerate() {
+		let line_end = line_start + line_size;
+		let class = file_map.dominant_class(line_start, line_end).unwrap_or(ByteClass::Null);
+
+		let mut marks_style = Style::default();
+		if line_start < viewport_end && viewport_start < line_end {
+			marks_style = marks_style.bg(Color::Indexed(238));
+		}
+
+		let search_mark = match search_lines[line] {
+			true => Span::styled("•", marks_style.fg(Color::White).bold()),
+			false => Span::styled(" ", marks_style)
+		};
+
+		let modified_mark = match modified_lines[line] {
+			true => Span::styled("*", marks_style.fg(Color::Red).bold()),
+			false => Span::styled(" ", marks_style)
+		};
+
+		list_items.push(ListItem::new(Line::from(vec![
+			Span::styled("██", Style::default().fg(class_color(class))),
+			search_mark,
+			modified_mark
+		])));
+	}
+
+	let list = List::new(list_items).block(minimap_block);
+	f.render_widget(list, pane);
+}
+
+/// Bar with a length proportional to the entropy (from 0 to 8 bits per byte)
+fn entropy_bar(entropy: f64) -> char {
+	const BARS: [char; 9] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+
+	BARS[(entropy.ceil() as usize).min(8)]
+}
+
+/// Red for compressed or encrypted data, grey for padding
+fn entropy_color(entropy: f64) -> Color {
+	match entropy {
+		entropy if entropy >= stats::HIGH_ENTROPY => Color::Red,
+		entropy if entropy >= 5.0 => Color::Yellow,
+		entropy if entropy >= stats::LOW_ENTROPY => Color::Cyan,
+		_ => Color::Indexed(242)
+	}
 }
 
 /// Render the address pane on the left
 fn render_address_block(app: &App, pane: Rect, f: &mut Frame) {
 	// top & bottom right corner must render the top & bottom left to join with the left block
-	let borders_address_block = symbols::border::Set {
+	let mut borders_address_block = symbols::border::Set {
 		top_right: symbols::line::NORMAL.horizontal_down,
 		bottom_right: symbols::line::NORMAL.horizontal_up,
 		..symbols::border::PLAIN
 	};
 
+	// same on the left, if the entropy pane is displayed
+	if app.show_entropy {
+		borders_address_block.top_left = symbols::line::NORMAL.horizontal_down;
+		borders_address_block.bottom_left = symbols::line::NORMAL.horizontal_up;
+	}
+
 	// Create the address block
 	let address_block = Block::default()
 		.border_set(borders_address_block) // make borders continous for the corners
@@ -124,22 +353,31 @@ fn render_address_block(app: &App, pane: Rect, f: &mut Frame) {
 	let remaining_file_size = app.length_to_end();
 
 	// don't write addresses after the last line
-	let mut end_address = match remaining_file_size < height * 16 {
+	let mut end_address = match remaining_file_size < height * app.bytes_per_line {
 		true  => start_address + remaining_file_size,
-		false => start_address + height*16
+		false => start_address + height * app.bytes_per_line
 	};
 
 	if app.mode == Mode::Insert {
 		end_address += 1;
 	}
 
-	for i in (start_address..end_address).step_by(16) {
-		list_items.push(
-			ListItem::new(Line::from(
-				Span::styled(format!("{:08x}", i),
-				Style::default().fg(Color::Indexed(242)))
-			)
-		));
+	for i in (start_address..end_address).step_by(app.bytes_per_line as usize) {
+		let mut line = vec![];
+
+		// show the first mark set on this line
+		if !app.marks.is_empty() {
+			let mark = app.marks.iter()
+				.find(|(_, mark)| mark.address / app.bytes_per_line == i / app.bytes_per_line)
+				.map_or(' ', |(name, _)| *name);
+
+			line.push(Span::styled(format!("{} ", mark), Style::default().fg(Color::Yellow).bold()));
+		}
+
+		let address = format!("{:0width$x}", app.display_address(i), width = app.address_width());
+		line.push(Span::styled(address, Style::default().fg(Color::Indexed(242))));
+
+		list_items.push(ListItem::new(Line::from(line)));
 	}
 
 	// add list to block, and render block
@@ -154,15 +392,20 @@ fn render_hex_block(app: &mut App, pane: Rect, f: &mut Frame) {
 
 	// Display the position of the cusror on the
 	// bottom of the hex block
-	let bottom_line = Line::from(
+	let mut bottom_line = Line::from(
 		vec![
-			format!(" 0x{:x}", app.cursor / 2).bold(),
+			format!(" 0x{:x}", app.display_address(app.cursor / app.digits())).bold(),
 			format!(" /{:x}", app.file_size).into(),
 			" ─ ".bold(),
 			format!("{} ", app.filename()).light_blue(),
 		]
 	);
 
+	// with a base address, the file offset is also displayed
+	if app.base_address != 0 {
+		bottom_line.spans.insert(1, format!(" (0x{:x})", app.cursor / app.digits()).dark_gray());
+	}
+
 	// We need to set the corners, to have continuous borders
 	let hexblock_borders = symbols::border::Set {
 		top_left: symbols::line::NORMAL.horizontal_down,
@@ -205,37 +448,42 @@ fn render_hex_block(app: &mut App, pane: Rect, f: &mut Frame) {
 		// We use this to build a line of hex chars
 		let mut line: Vec<Span> = vec![];
 
+		// the bytes of the line are read first, the groups in little endian are displayed backward
+		let (bytes, _) = app.read_line_length();
+		let line_start = app.offset + u64::from(l) * app.bytes_per_line;
+
 		// Render a line of the Hex pane
-		for i in 0..0x10 {
+		for i in 0..app.bytes_per_line {
 
 			// Colorize space between hex chars if the previous and next chars are selected
 			// or if the previous and next chars are part of a search result
 			// otherwise, just push a " " without background color
-			let current_byte: u64 = app.offset + u64::from(l) * 0x10 + i;
+			let byte_addr = line_start + app.displayed_index(i);
+			let previous_byte_addr = line_start + app.displayed_index(i.saturating_sub(1));
 			
-			if i == 0 || i == 8 { // don't colorize the initial, and the separator
-				line.push(Span::raw(" "));
-			}
-			// colorize the space between 2 bytes if selected
-			else if app.is_selected(current_byte.saturating_sub(1)) && app.is_selected(current_byte) {
-				line.push(Span::raw(" ").bg(Color::Indexed(238)));
-			}
-			// colorize the space between 2 bytes if searched
-			else if app.is_searched(current_byte.saturating_sub(1)) && app.is_searched(current_byte) {
-				line.push(Span::raw(" ").style(SEACHED_STYLE));
-			}
-			else {
-				line.push(Span::raw(" "))
+			// no space inside a group of bytes
+			if i % app.group_size == 0 {
+				if i % 8 == 0 { // don't colorize the initial, and the separators
+					line.push(Span::raw(" "));
+				}
+				// colorize the space between 2 bytes if selected
+				else if app.is_selected(previous_byte_addr) && app.is_selected(byte_addr) {
+					line.push(Span::raw(" ").bg(Color::Indexed(238)));
+				}
+				// colorize the space between 2 bytes if searched
+				else if app.is_searched(previous_byte_addr) && app.is_searched(byte_addr) {
+					line.push(Span::raw(" ").style(SEACHED_STYLE));
+				}
+				else {
+					line.push(Span::raw(" "))
+				}
 			}
 
-			
-
-			let byte = app.read_byte();
-			let byte_addr = app.last_address_read - 1;
+			let byte = bytes.get((byte_addr - line_start) as usize).copied();
 
 			match byte {
 				// We are the cursor, after the end of the file
-				None if app.cursor / 2 == byte_addr => {
+				None if app.cursor / app.digits() == byte_addr => {
 					let style = Style::default().fg(Color::White);
 					let style_focused = style.bg(Color::DarkGray);
 
@@ -243,16 +491,16 @@ fn render_hex_block(app: &mut App, pane: Rect, f: &mut Frame) {
 						true  => line.push(Span::styled("_", style_focused)),
 						false => line.push(Span::styled("_", style))
 					};
-					line.push(Span::raw(" "));
+					line.push(Span::raw(" ".repeat(app.digits() as usize - 1)));
 				},
 
 				// We have reach EOF, pad with some empty spaces
-				None => line.push(Span::raw("  ")),
+				None => line.push(Span::raw(" ".repeat(app.digits() as usize))),
 
 				// We have a byte to display
 				Some(val) => {
 					// Is this the byte with the cursor ?
-					match app.cursor / 2 == byte_addr {
+					match app.cursor / app.digits() == byte_addr {
 
 						// It's not the cursor
 						false => {
@@ -264,7 +512,7 @@ fn render_hex_block(app: &mut App, pane: Rect, f: &mut Frame) {
 							};
 
 							line.push(Span::styled(
-								format!("{:02x}", val),
+								app.view.format(val),
 								style
 							))
 						},
@@ -277,7 +525,7 @@ fn render_hex_block(app: &mut App, pane: Rect, f: &mut Frame) {
 							let cursor_background = match focused {
 								true if val == 0x00 => Color::White,
 								true  => get_color(val),
-								false if app.is_searched(app.cursor / 2) => Color::Yellow,
+								false if app.is_searched(app.cursor / app.digits()) => Color::Yellow,
 								false => Color::Black,
 							};
 
@@ -295,7 +543,7 @@ fn render_hex_block(app: &mut App, pane: Rect, f: &mut Frame) {
 							// Style byte that is not highlighted by the cursor
 							let other_style = match (
 								app.is_selected_cursor(app.cursor),
-								app.is_searched(app.cursor / 2)
+								app.is_searched(app.cursor / app.digits())
 							) {
 								(true, _) => colorize(val).bg(Color::Indexed(238)), // Selection background color
  								(_, true) => SEACHED_STYLE, // Searched background color
@@ -304,35 +552,26 @@ fn render_hex_block(app: &mut App, pane: Rect, f: &mut Frame) {
 
 							/* Apply the style of the cursor to the corresponding char */
 
-							let (style_char1, style_char2) = match app.cursor % 2 == 0 {
-	
-								// if the ascii pane is focused, we hightlight both chars corresponding to the
-								// byte selected with the cursor on the ascii pane
-								_ if !focused => (cursor_style, cursor_style),
-
-								// cursor is on the first char
-								true  => (cursor_style, other_style),
-								// cursor is on the second char
-								false => (other_style, cursor_style)
-							};
-
-
-							/* Finally add this to the UI */
+							for (position, digit) in app.view.format(val).chars().enumerate() {
+								let style = match position as u64 == app.cursor % app.digits() {
+									// if the ascii pane is focused, we hightlight all the digits corresponding to the
+									// byte selected with the cursor on the ascii pane
+									_ if !focused => cursor_style,
 
-							// Get the 2 chars of the cursor
-							let hex_val = format!("{:02x?}", val);
-							let hex_char1 = hex_val.chars().nth(0).unwrap();
-							let hex_char2 = hex_val.chars().nth(1).unwrap();
+									// the cursor is on this digit
+									true  => cursor_style,
+									false => other_style
+								};
 
-							line.push(Span::styled(hex_char1.to_string(), style_char1));
-							line.push(Span::styled(hex_char2.to_string(), style_char2));
+								line.push(Span::styled(digit.to_string(), style));
+							}
 						}
 					}
 				}
 			}
 
-			// add the stylish ┊ in the middle, color changes in hexyl mode
-			if i == 7 {
+			// add the stylish ┊ every 8 bytes, color changes in hexyl mode
+			if i % 8 == 7 && i + 1 < app.bytes_per_line {
 				let separator_style = match app.show_infobar {
 					false => Style::default(),
 					true => Style::default().fg(Color::DarkGray),
@@ -403,33 +642,27 @@ fn render_ascii_block(app: &mut App, pane: Rect, f: &mut Frame) {
 
 	for line in 0..app.lines_displayed {
 
-		// Convert the bytes to an array.
-		// We might want to change this in the future.
-		// This is because the app use to read 16 bytes into an array. And all the function
-		// were build using an array.
-		let (content, len) = app.read_16_length();
-		let mut buf: [u8; 16] = [0; 16];
-
-		for i in 0..len {
-			buf[i] = content[i];
-		}
+		let (buf, len) = app.read_line_length();
+		let bytes_per_line = app.bytes_per_line as usize;
+		let line_start = app.offset + u64::from(line) * app.bytes_per_line;
+		let chars = render_text_line(app, line_start, &buf[..len]);
 
 		// if this is the line with the cursor
-		if (app.offset / 16) + u64::from(line) == app.cursor / 32 {
+		if (app.offset / app.bytes_per_line) + u64::from(line) == app.cursor / app.line_digits() {
 
-			let line_cursor = app.cursor % 32;
-			let cursor = (line_cursor / 2).try_into().unwrap();
+			let line_cursor = app.cursor % app.line_digits();
+			let cursor = (line_cursor / app.digits()).try_into().unwrap();
 			let focused = app.editor_mode == CurrentEditor::AsciiEditor;
 
 			let mut ascii_colorized: Vec<Span> = vec![];
 
 
-			for i in 0..16 {
+			for i in 0..bytes_per_line {
 				if i < len { // display at most `len` chars
 								
 					if i == cursor { // highlight the cursor
 
-						let searched = app.is_searched(app.offset + u64::from(line) * 16 + i as u64);
+						let searched = app.is_searched(line_start + i as u64);
 
 						let mut style = match (focused, searched) {
 							(true, _) => Style::default()
@@ -443,16 +676,20 @@ fn render_ascii_block(app: &mut App, pane: Rect, f: &mut Frame) {
 							style = style.bg(Color::White);
 						}
 
-						let colorized = Span::styled(
-							render_ascii_char(buf[i], app.braille).to_string(),
-							style
-						);			
+						// the cursor is on a byte covered by a wide char, highlight the char
+						if chars[i].content.is_empty() {
+							if let Some(previous) = ascii_colorized.last_mut() {
+								previous.style = style;
+							}
+						}
+
+						let colorized = Span::styled(chars[i].content.clone(), style);
 
 						ascii_colorized.push(colorized);
 
 					} else {
-						let mut colorized_ascii = render_ascii_char(buf[i], app.braille);
-						if app.is_searched(app.offset + u64::from(line) * 16 + i as u64) {
+						let mut colorized_ascii = chars[i].clone();
+						if app.is_searched(line_start + i as u64) {
 							colorized_ascii = colorized_ascii.style(SEACHED_STYLE);
 						}
 
@@ -476,7 +713,7 @@ fn render_ascii_block(app: &mut App, pane: Rect, f: &mut Frame) {
 					ascii_colorized.push(Span::raw(" "));
 				}
 				
-				if i == 7 { // stylish ┊ in the middle
+				if i % 8 == 7 && i + 1 < bytes_per_line { // stylish ┊ every 8 bytes
 					let separator_style = match !app.show_infobar {
 						true  => {Style::default()},
 						false => {Style::default().fg(Color::DarkGray)},
@@ -490,10 +727,10 @@ fn render_ascii_block(app: &mut App, pane: Rect, f: &mut Frame) {
 				
 		} else {	// Ascii line without anything special
 			let mut ascii_colorized: Vec<Span> = vec![];
-			for i in 0..16 {
+			for i in 0..bytes_per_line {
 				if i < len {
-					let mut colorized_char = render_ascii_char(buf[i], app.braille);
-					if app.is_searched(app.offset + u64::from(line) * 16 + i as u64) {
+					let mut colorized_char = chars[i].clone();
+					if app.is_searched(line_start + i as u64) {
 						colorized_char = colorized_char.style(SEACHED_STYLE);
 					}
 					ascii_colorized.push(colorized_char);
@@ -501,7 +738,7 @@ fn render_ascii_block(app: &mut App, pane: Rect, f: &mut Frame) {
 					ascii_colorized.push(Span::raw(" "));
 				}
 
-				if i == 7 {
+				if i % 8 == 7 && i + 1 < bytes_per_line {
 					let separator_style = match !app.show_infobar {
 						true  => Style::default(),
 						false => Style::default().fg(Color::DarkGray),
@@ -555,6 +792,39 @@ fn render_preview_block(app: &mut App, pane: Rect, f: &mut Frame) {
 	lines.push(bytes_header);
 	lines.push(bytes);
 
+	// Hash of the selected bytes, requested with `:hash`
+	app.update_preview_digest();
+	if let Some(preview_digest) = &app.preview_digest {
+		let digest: String = preview_digest.digest
+			.iter()
+			.map(|byte| format!("{:02x}", byte))
+			.collect();
+
+		lines.push(Line::from(""));
+		lines.push(Line::from(format!("{}:", preview_digest.algorithm.name()).green().bold()));
+		lines.push(Line::from(digest));
+	}
+
+	// Preview the transformation typed in the command bar, i.e `:xor 41`
+	if let Some(command_bar) = &app.command_bar {
+		if let Ok(transform) = Transform::parse(&command_bar.command) {
+			// only transform the bytes that fit in the pane, 2 chars for each byte
+			let max_bytes = usize::from(pane.width) * usize::from(pane.height) / 2;
+
+			let mut transformed_string: String = transform.apply_start(&selected_bytes, max_bytes)
+				.iter()
+				.map(|byte| format!("{:02x}", byte))
+				.collect();
+			if number_of_bytes > max_bytes {
+				transformed_string.push('…');
+			}
+
+			let preview_header = format!("Preview {}:", command_bar.command.trim());
+			lines.push(Line::from(""));
+			lines.push(Line::from(preview_header.yellow().bold()));
+			lines.push(Line::from(transformed_string));
+		}
+	}
 
 	if number_of_bytes <= 16 {
 
@@ -729,6 +999,79 @@ fn render_preview_block(app: &mut App, pane: Rect, f: &mut Frame) {
 		lines.push(hex_be_line);
 	}
 
+	// Floating point numbers, if the selection has the size of one
+	if matches!(number_of_bytes, 2 | 4 | 8) {
+		lines.push(Line::from(""));
+		lines.push(Line::from(format!("Floats: ({} bits)", number_of_bytes * 8).red().bold()));
+
+		for endianness in [Endianness::Little, Endianness::Big] {
+			let field = inspector::Field { kind: Kind::Float(number_of_bytes), endianness };
+			let value = field.decode(&selected_bytes).unwrap_or_default();
+
+			lines.push(
+				format!("{}: ", field.name()).blue().bold() + value.into()
+			);
+		}
+
+		// bfloat16 is the upper half of a f32
+		if number_of_bytes == 2 {
+			let bytes: [u8; 2] = selected_bytes[0..2].try_into().unwrap();
+
+			for (name, bits) in [("bf16le", u16::from_le_bytes(bytes)), ("bf16be", u16::from_be_bytes(bytes))] {
+				let value = f32::from_bits(u32::from(bits) << 16);
+
+				lines.push(
+					format!("{}: ", name).magenta().bold() + format!("{}", value).into()
+				);
+			}
+		}
+	}
+
+	// Strings, cut to what the pane can show: the selection can be big
+	if number_of_bytes > 1 {
+		lines.push(Line::from(""));
+		lines.push(Line::from("Strings:".red().bold()));
+
+		let max_chars = usize::from(pane.width) * usize::from(pane.height);
+
+		// C string, stops at the first null byte. Each byte is at least one char once escaped
+		let c_string = selected_bytes.split(|byte| *byte == 0).next().unwrap_or_default();
+		let c_string = escape_bytes(&c_string[..min(c_string.len(), max_chars + 1)]);
+		lines.push(
+			"C string: ".blue().bold() + format!("\"{}\"", truncate_preview(c_string.chars(), max_chars)).into()
+		);
+
+		// UTF-16, only if the bytes shown are valid
+		if number_of_bytes.is_multiple_of(2) {
+			for (name, endianness) in [("utf16le", Endianness::Little), ("utf16be", Endianness::Big)] {
+				let units = selected_bytes.chunks_exact(2)
+					.map(|unit| match endianness {
+						Endianness::Little => u16::from_le_bytes([unit[0], unit[1]]),
+						Endianness::Big    => u16::from_be_bytes([unit[0], unit[1]]),
+					});
+
+				let utf16_string: Result<String, _> = char::decode_utf16(units).take(max_chars + 1).collect();
+				if let Ok(utf16_string) = utf16_string {
+					lines.push(
+						format!("{}: ", name).magenta().bold() + truncate_preview(utf16_string.chars().map(text_char), max_chars).into()
+					);
+				}
+			}
+		}
+
+		// Latin-1, every byte is a char
+		let latin1_string = truncate_preview(selected_bytes.iter().map(|byte| text_char(*byte as char)), max_chars);
+		lines.push(
+			"latin1: ".green().bold() + latin1_string.into()
+		);
+
+		// Escaped bytes, can be pasted in C, Rust or Python
+		let escaped = escape_bytes(&selected_bytes[..min(number_of_bytes, max_chars + 1)]);
+		lines.push(
+			"escaped: ".yellow().bold() + format!("\"{}\"", truncate_preview(escaped.chars(), max_chars)).into()
+		);
+	}
+
 	// ASCII
 	if let Ok(utf8_string) = String::from_utf8(selected_bytes) {
 		let string_header = Line::from(format!("\n\nUTF-8 text:\n ({} bits)", number_of_bytes * 8).red().bold());
@@ -737,15 +1080,91 @@ fn render_preview_block(app: &mut App, pane: Rect, f: &mut Frame) {
 
 		lines.push(Line::from(utf8_string));	
 	}
-	
+
+	// Scroll the preview with `[` and `]` when it doesn't fit in the pane
+	let inner_width = pane.width.saturating_sub(1).max(1) as usize;
+	let inner_height = pane.height.saturating_sub(2) as usize;
+	let wrapped_lines: usize = lines.iter()
+		.map(|line| line.width().div_ceil(inner_width).max(1))
+		.sum();
+
+	let max_scroll = wrapped_lines.saturating_sub(inner_height);
+	app.preview_scroll = min(app.preview_scroll, max_scroll as u16);
+
+	let preview_block = if max_scroll > 0 {
+		preview_block.title_bottom(Line::from(" [ ] to scroll ").centered())
+	} else {
+		preview_block
+	};
+
 	let text = Text::from(lines);
 	let paragraph = Paragraph::new(text)
 	.block(preview_block)
-	.wrap(Wrap {trim: true});
+	.wrap(Wrap {trim: true})
+	.scroll((app.preview_scroll, 0));
 
 	f.render_widget(paragraph, pane);
 }
 
+/// Render the inspector pane on the right, with the bytes at the cursor decoded in every field
+fn render_inspector_block(app: &mut App, pane: Rect, f: &mut Frame) {
+	let inspector_block = Block::default()
+		.borders(Borders::TOP | Borders::RIGHT | Borders::BOTTOM)
+		.title(format!(" 0x{:x} ", app.display_address(app.cursor / app.digits())))
+		.title_alignment(ratatui::layout::Alignment::Center);
+
+	// enough bytes for the biggest field, and the longest LEB128
+	let bytes = app.cursor_bytes(16);
+	let width = pane.width.saturating_sub(1) as usize;
+
+	let mut lines: Vec<Line> = vec![];
+	let mut selected_line = 0;
+
+	for (index, field) in inspector::fields().into_iter().enumerate() {
+		let name_color = match field.kind {
+			Kind::Unsigned(_) | Kind::Uleb128 => Color::Blue,
+			Kind::Signed(_) | Kind::Sleb128 | Kind::Varint => Color::Magenta,
+			Kind::Float(_) => Color::Green,
+			_ => Color::Red
+		};
+		let name = format!("{:<9}", field.name()).fg(name_color).bold();
+
+		let value = match field.decode(&bytes) {
+			Some(value) => Span::raw(value),
+			None => "-".dark_gray()
+		};
+
+		let first_line = lines.len();
+
+		// the long values, like the GUIDs, go under their name
+		if name.width() + value.width() > width {
+			lines.push(Line::from(name));
+			lines.push(Line::from(value));
+		} else {
+			lines.push(Line::from(vec![name, value]));
+		}
+
+		// highlight the field selected to be edited
+		if app.editor_mode == CurrentEditor::Inspector && index == app.inspector_index {
+			for line in &mut lines[first_line..] {
+				let padding = " ".repeat(width.saturating_sub(line.width()));
+				line.push_span(Span::raw(padding));
+				*line = line.clone().bg(Color::Indexed(238));
+			}
+			selected_line = lines.len() - 1;
+		}
+	}
+
+	// scroll to keep the selected field on screen
+	let height = pane.height.saturating_sub(2) as usize;
+	let scroll = (selected_line + 1).saturating_sub(height) as u16;
+
+	let paragraph = Paragraph::new(Text::from(lines))
+		.block(inspector_block)
+		.scroll((scroll, 0));
+	f.render_widget(paragraph, pane);
+}
+
 /// Display the command bar or an error message, as one line at the end of the UI.
 /// This function exists to reduce code duplication.
 fn render_command_bar(text: String, style: Style, f: &mut Frame) {
@@ -861,6 +1280,60 @@ fn render_command_bar(text: String, style: Style, f: &mut Frame) {
 	// }
 
 
+/// Used for the ascii pane.
+/// Render the bytes of a line with `app.encoding`, one span for each byte. A wide char
+/// is displayed over the next byte of the char, which gets an empty span
+fn render_text_line(app: &mut App, address: u64, bytes: &[u8]) -> Vec<Span<'static>> {
+	if app.encoding == Encoding::Ascii {
+		return bytes.iter().map(|byte| render_ascii_char(*byte, app.braille)).collect();
+	}
+
+	let glyphs = app.decode_text(address, bytes);
+	let mut covered = false; // the previous char is displayed over this byte
+
+	glyphs.iter().enumerate().map(|(i, glyph)| {
+		let text = match glyph {
+			Glyph::Continuation if covered => String::new(),
+			Glyph::Continuation => "·".to_string(),
+			Glyph::Invalid => "x".to_string(),
+			Glyph::Char(c) => {
+				let text = text_char(*c).to_string();
+
+				match Span::raw(text.as_str()).width() {
+					1 => text,
+					// don't cover the ┊ separator
+					2 if glyphs.get(i + 1) == Some(&Glyph::Continuation) && i % 8 != 7 => text,
+					_ => "•".to_string()
+				}
+			}
+		};
+		covered = Span::raw(text.as_str()).width() == 2;
+
+		Span::styled(text, get_color(bytes[i]))
+	}).collect()
+}
+
+/// The first `max_chars` chars of a string displayed in the preview pane, followed by `…` if it is longer
+fn truncate_preview(mut chars: impl Iterator<Item = char>, max_chars: usize) -> String {
+	let mut text: String = chars.by_ref().take(max_chars).collect();
+
+	if chars.next().is_some() {
+		text.push('…');
+	}
+	text
+}
+
+/// Take a decoded char, return it or a placeholder, like `ascii_char()`
+fn text_char(c: char) -> char {
+	match c {
+		'\0' => '0',
+		' ' => ' ',
+		c if c.is_whitespace() => '_',
+		c if c.is_control() => '•',
+		c => c
+	}
+}
+
 /// Used for the ascii pane.
 /// Take a u8, and render a colorized ascii, or placeholdler
 fn render_ascii_char(val: u8, braille: Braille) -> Span<'static> {
@@ -918,25 +1391,47 @@ fn colorize(val: u8) -> Style {
 }
 
 fn get_color(val: u8) -> Color {
-	match val {
-		val if val == 0x00 => {
-			Color::Indexed(242)
-		},
-		val if val.is_ascii_whitespace() => {
-			Color::Green
-		},
-		val if val > 0x20 && val < 0x7f => {
-			Color::Cyan
-		},
-		val if val.is_ascii() => {
-			Color::Magenta
-		},
-		_val => {
-			Color::Yellow
-		}
+	class_color(ByteClass::of(val))
+}
+
+fn class_color(class: ByteClass) -> Color {
+	match class {
+		ByteClass::Null => Color::Indexed(242),
+		ByteClass::Whitespace => Color::Green,
+		ByteClass::Printable => Color::Cyan,
+		ByteClass::OtherAscii => Color::Magenta,
+		ByteClass::High => Color::Yellow
 	}
 }
 
+/// Popup displaying the lines of `list_popup`, like the list of marks
+fn list_popup_block(list_popup: &ListPopup, f: &mut Frame) {
+	let area = f.area();
+
+	let popup_area = Rect {
+		x: area.width / 4,
+		y: area.height / 6,
+		width: area.width / 2,
+		height: (list_popup.lines.len() as u16 + 2).min(area.height.saturating_sub(area.height / 6)),
+	};
+
+	let lines: Vec<Line> = list_popup.lines.iter()
+		.map(|line| Line::from(line.as_str()))
+		.collect();
+
+	let popup = Paragraph::new(lines)
+		.block(
+			Block::new()
+				.title(list_popup.title.as_str())
+				.title_style(Style::new().white().bold())
+				.borders(Borders::ALL)
+				.border_style(Style::new().blue()),
+		);
+
+	f.render_widget(Clear, popup_area);
+	f.render_widget(popup, popup_area);
+}
+
 fn exit_popup(f: &mut Frame) {
 	let area = f.area();
 
@@ -969,4 +1464,117 @@ fn exit_popup(f: &mut Frame) {
 	
 	f.render_widget(Clear, popup_area); //this clears the entire screen and anything already drawn
 	f.render_widget(popup, popup_area);
-}
\ No newline at end of file
+}
+
+/// Popup opened by `:stats`: histogram of the byte values, and statistics on the bytes
+fn stats_popup(byte_stats: &ByteStats, base_address: u64, f: &mut Frame) {
+	let area = f.area();
+
+	let start = byte_stats.start.saturating_add(base_address);
+	let end = byte_stats.end.saturating_add(base_address);
+
+	let popup_area = Rect {
+		x: 2.min(area.width),
+		y: 1.min(area.height),
+		width: area.width.saturating_sub(4),
+		height: area.height.saturating_sub(3).min(26),
+	};
+
+	let popup_block = Block::new()
+		.title(format!(" Statistics of {}: 0x{:x}..0x{:x} ", byte_stats.description, start, end))
+		.title_style(Style::new().white().bold())
+		.borders(Borders::ALL)
+		.border_style(Style::new().blue());
+
+	let inner_area = popup_block.inner(popup_area);
+
+	f.render_widget(Clear, popup_area);
+	f.render_widget(popup_block, popup_area);
+
+	let [histogram_area, axis_area, text_area] = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([Constraint::Min(3), Constraint::Length(1), Constraint::Length(8)])
+		.areas(inner_area);
+
+	/* Histogram: as many bytes values as possible in each bar */
+	let values_per_bar = (1..=256usize)
+		.find(|values| 256usize.div_ceil(*values) <= histogram_area.width as usize)
+		.unwrap_or(256);
+
+	let bars: Vec<Bar> = byte_stats.counts
+		.chunks(values_per_bar)
+		.enumerate()
+		.map(|(i, counts)| {
+			let first_value = (i * values_per_bar) as u8;
+			Bar::new(counts.iter().sum())
+				.text_value("")
+				.style(colorize(first_value))
+		})
+		.collect();
+
+	let histogram_width = bars.len() as u16;
+
+	let histogram = BarChart::vertical(bars)
+		.bar_width(1)
+		.bar_gap(0);
+
+	f.render_widget(histogram, histogram_area);
+
+	// values of the bytes under the histogram
+	let axis_width = histogram_width.min(axis_area.width) as usize;
+	let axis = Line::from(vec![
+		Span::raw(format!("{:<width$}", "00", width = axis_width / 2)),
+		Span::raw(format!("{:<width$}", "80", width = (axis_width - axis_width / 2).saturating_sub(2))),
+		Span::raw("ff")
+	]).fg(Color::Indexed(242));
+	f.render_widget(Paragraph::new(axis), axis_area);
+
+	/* Count of the bytes of each class, with the colors of the hex pane */
+	let [classes_area, summary_area] = Layout::default()
+		.direction(Direction::Horizontal)
+		.constraints([Constraint::Length(32), Constraint::Min(0)])
+		.areas(text_area);
+
+	let total = byte_stats.total();
+	let percentage = |count: u64| match total {
+		0 => 0.0,
+		total => count as f64 * 100.0 / total as f64
+	};
+
+	let mut class_lines = vec![Line::from("")];
+	for class in ByteClass::ALL {
+		let count = byte_stats.class_count(class);
+
+		class_lines.push(Line::from(vec![
+			Span::styled(format!("{:<12}", class.name()), Style::new().fg(class_color(class))),
+			Span::raw(format!("{:>10} {:>6.2}%", count, percentage(count)))
+		]));
+	}
+	f.render_widget(Paragraph::new(class_lines), classes_area);
+
+	/* Summary */
+	let hex_value = |value: Option<u8>| value.map_or("-".to_string(), |value| format!("0x{:02x}", value));
+
+	let most_common: Vec<Span> = byte_stats.most_common(5)
+		.iter()
+		.flat_map(|(byte, count)| [
+			Span::styled(format!("{:02x}", byte), colorize(*byte)),
+			Span::raw(format!(" ({:.2}%)  ", percentage(*count)))
+		])
+		.collect();
+
+	let summary_lines = vec![
+		Line::from(""),
+		Line::from(format!("Bytes:    0x{:x} ({})", total, total)),
+		Line::from(format!("Min:      {}", hex_value(byte_stats.min()))),
+		Line::from(format!("Max:      {}", hex_value(byte_stats.max()))),
+		Line::from(format!("Mean:     {}", byte_stats.mean().map_or("-".to_string(), |mean| format!("{:.2}", mean)))),
+		Line::from(vec![
+			Span::raw("Entropy:  "),
+			Span::styled(format!("{:.3}", byte_stats.entropy()), Style::new().fg(entropy_color(byte_stats.entropy()))),
+			Span::raw(" bits per byte")
+		]),
+		Line::from([vec![Span::raw("Common:   ")], most_common].concat())
+	];
+	f.render_widget(Paragraph::new(summary_lines), summary_area);
+}